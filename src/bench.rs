@@ -0,0 +1,278 @@
+// bench.rs
+use crate::hands::*;
+use crate::poker::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// Times calculate_hand against the HashMap based evaluator it replaced,
+// on the same random 7 card hands. Run it with --release.
+pub fn run(hands: usize) {
+    let samples = random_hands(hands, 7, 0);
+
+    let fast = time(&samples, calculate_hand);
+    let slow = time(&samples, |cards| hash_map_hand(cards, &WHEEL));
+    println!("{} hands of 7 cards", hands);
+    println!(
+        "calculate_hand: {:?} ({:.1} ns/hand)",
+        fast,
+        per_hand(fast, hands)
+    );
+    println!(
+        "HashMap evaluator: {:?} ({:.1} ns/hand)",
+        slow,
+        per_hand(slow, hands)
+    );
+    println!("{:.1}x faster", slow.as_secs_f64() / fast.as_secs_f64());
+}
+
+fn time(samples: &[Vec<Card>], evaluate: impl Fn(&[Card]) -> Hand) -> Duration {
+    let start = Instant::now();
+    for cards in samples.iter() {
+        black_box(evaluate(black_box(cards)));
+    }
+    start.elapsed()
+}
+
+fn per_hand(duration: Duration, hands: usize) -> f64 {
+    duration.as_nanos() as f64 / hands as f64
+}
+
+// Random hands of the given size, the same ones for the same seed
+pub fn random_hands(hands: usize, size: usize, seed: u64) -> Vec<Vec<Card>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut deck: Vec<Card> = SUITS
+        .iter()
+        .flat_map(|&suit| RANKS.iter().map(move |&rank| Card { rank, suit }))
+        .collect();
+    (0..hands)
+        .map(|_| {
+            deck.shuffle(&mut rng);
+            deck[..size].to_vec()
+        })
+        .collect()
+}
+
+// The original evaluator, kept to benchmark and test calculate_hand against
+pub fn hash_map_hand(cards: &[Card], wheel: &[Rank; 4]) -> Hand {
+    if let Some(hand) = is_straight_flush(cards, wheel) {
+        if hand == Hand::StraightFlush(Rank::Ace) {
+            return Hand::RoyalFlush;
+        } else {
+            return hand;
+        }
+    } else if let Some(hand) = is_four_of_a_kind(cards) {
+        return hand;
+    } else if let Some(hand) = is_full_house(cards) {
+        return hand;
+    } else if let Some(hand) = is_flush(cards) {
+        return hand;
+    } else if let Some(hand) = is_straight(cards, wheel) {
+        return hand;
+    } else if let Some(hand) = is_three_of_a_kind(cards) {
+        return hand;
+    } else if let Some(hand) = is_pairs(cards) {
+        return hand;
+    }
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort();
+    let l = ranks.len();
+    Hand::HighCard(
+        ranks[l - 1],
+        ranks[l - 2],
+        ranks[l - 3],
+        ranks[l - 4],
+        ranks[l - 5],
+    )
+}
+
+//
+// Functions to check if a set of cards can be made into each hand
+//
+fn is_straight_flush(cards: &[Card], wheel: &[Rank; 4]) -> Option<Hand> {
+    // separate cards by suit:
+    let mut suits: HashMap<Suit, Vec<Card>> = HashMap::new();
+    for card in cards.iter() {
+        suits.entry(card.suit).or_default().push(*card);
+    }
+    // check for straights in each suit
+    for (_, card_vec) in suits.iter() {
+        if card_vec.len() < 5 {
+            continue;
+        } else if let Some(Hand::Straight(rank)) = is_straight(card_vec.as_slice(), wheel) {
+            return Some(Hand::StraightFlush(rank));
+        }
+    }
+    None
+}
+
+fn is_four_of_a_kind(cards: &[Card]) -> Option<Hand> {
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort();
+    // separate cards by rank:
+    let mut counts: HashMap<Rank, usize> = HashMap::new();
+    for rank in ranks.iter() {
+        *counts.entry(*rank).or_insert(0) += 1;
+    }
+    for (rank, count) in counts.iter() {
+        if *count == 4 {
+            let mut other = ranks.clone();
+            other.retain(|r| r != rank);
+            other.sort();
+            return Some(Hand::FourOfAKind(*rank, other[other.len() - 1]));
+        }
+    }
+    None
+}
+
+fn is_full_house(cards: &[Card]) -> Option<Hand> {
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort();
+    // separate cards by rank:
+    let mut counts: HashMap<Rank, usize> = HashMap::new();
+    for rank in ranks.iter() {
+        *counts.entry(*rank).or_insert(0) += 1;
+    }
+    // find the highest trio, then the highest pair among the other ranks.
+    // With two trios the lower one is used as the pair.
+    let trio = counts
+        .iter()
+        .filter(|(_, count)| **count == 3)
+        .map(|(rank, _)| *rank)
+        .max();
+    let pair = counts
+        .iter()
+        .filter(|(rank, count)| **count >= 2 && Some(**rank) != trio)
+        .map(|(rank, _)| *rank)
+        .max();
+    if let (Some(trio), Some(pair)) = (trio, pair) {
+        Some(Hand::FullHouse(trio, pair))
+    } else {
+        None
+    }
+}
+
+fn is_flush(cards: &[Card]) -> Option<Hand> {
+    // separate cards by suit:
+    let mut suits: HashMap<Suit, Vec<Card>> = HashMap::new();
+    for card in cards.iter() {
+        suits.entry(card.suit).or_default().push(*card);
+    }
+    // check for flushes in each suit
+    for (_, card_vec) in suits.iter() {
+        if card_vec.len() < 5 {
+            continue;
+        }
+        let mut ranks = card_vec.iter().map(|c| c.rank).collect::<Vec<_>>();
+        ranks.sort();
+        let l = ranks.len();
+        return Some(Hand::Flush(
+            ranks[l - 1],
+            ranks[l - 2],
+            ranks[l - 3],
+            ranks[l - 4],
+            ranks[l - 5],
+        ));
+    }
+    None
+}
+
+fn is_straight(cards: &[Card], wheel: &[Rank; 4]) -> Option<Hand> {
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort();
+    // remove duplicates
+    ranks.dedup();
+    if ranks.len() < 5 {
+        return None;
+    }
+    // check for straights in each subset of length 5
+    for i in (0..=(ranks.len() - 5)).rev() {
+        let mut straight = true;
+        for j in i..(i + 5 - 1) {
+            if (ranks[j] as u8 + 1) != (ranks[j + 1] as u8) {
+                straight = false;
+                break;
+            }
+        }
+        if straight {
+            return Some(Hand::Straight(ranks[i + 5 - 1]));
+        }
+    }
+    // special case for ace low straight, only if there's no higher straight
+    if wheel.iter().all(|r| ranks.contains(r)) && ranks.contains(&Rank::Ace) {
+        return Some(Hand::Straight(wheel[3]));
+    }
+    None
+}
+
+fn is_three_of_a_kind(cards: &[Card]) -> Option<Hand> {
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort();
+    // separate cards by rank:
+    let mut counts: HashMap<Rank, usize> = HashMap::new();
+    for rank in ranks.iter() {
+        *counts.entry(*rank).or_insert(0) += 1;
+    }
+    for (rank, count) in counts.iter() {
+        if *count == 3 {
+            let mut other = ranks.clone();
+            other.retain(|r| r != rank);
+            other.sort();
+            return Some(Hand::ThreeOfAKind(
+                *rank,
+                other[other.len() - 1],
+                other[other.len() - 2],
+            ));
+        }
+    }
+    None
+}
+
+fn is_pairs(cards: &[Card]) -> Option<Hand> {
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort();
+    // separate cards by rank:
+    let mut counts: HashMap<Rank, usize> = HashMap::new();
+    for rank in ranks.iter() {
+        *counts.entry(*rank).or_insert(0) += 1;
+    }
+    let mut pairs = Vec::new();
+    for (rank, count) in counts.iter() {
+        if *count == 2 {
+            pairs.push(*rank);
+        }
+    }
+    // handle 3 pair case, select highest 2 pairs
+    if pairs.len() == 3 {
+        let mut other = ranks.clone();
+        other.retain(|r| r != &pairs[0] && r != &pairs[1] && r != &pairs[2]);
+        other.sort();
+        pairs.sort();
+        if pairs[0] > other[other.len() - 1] {
+            Some(Hand::TwoPair(pairs[2], pairs[1], pairs[0]))
+        } else {
+            Some(Hand::TwoPair(pairs[2], pairs[1], other[other.len() - 1]))
+        }
+    } else if pairs.len() == 2 {
+        let mut other = ranks.clone();
+        other.retain(|r| r != &pairs[0] && r != &pairs[1]);
+        other.sort();
+        pairs.sort();
+        Some(Hand::TwoPair(pairs[1], pairs[0], other[other.len() - 1]))
+    } else if pairs.len() == 1 {
+        let mut other = ranks.clone();
+        other.retain(|r| r != &pairs[0]);
+        other.sort();
+        Some(Hand::Pair(
+            pairs[0],
+            other[other.len() - 1],
+            other[other.len() - 2],
+            other[other.len() - 3],
+        ))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,85 @@
+// betting.rs
+use crate::poker::*;
+
+// How much a player can raise.
+// NoLimit: any raise at least as big as the last one, up to the whole stack.
+// PotLimit: same minimum, up to the size of the pot after calling.
+// Limit: fixed-limit, raises of exactly one bet, the small bet before the turn and
+// twice that after it, with at most MAX_BETS bets on each street.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Betting {
+    #[default]
+    NoLimit,
+    PotLimit,
+    Limit,
+}
+
+// A bet and three raises. Preflop the big blind counts as the bet.
+pub const MAX_BETS: u32 = 4;
+
+impl Betting {
+    // Size of a fixed-limit bet on the street, given the small blind or bring-in
+    pub fn limit_bet(&self, round: Round, min_bet: u32) -> u32 {
+        match round {
+            Round::Turn
+            | Round::River
+            | Round::FifthStreet
+            | Round::SixthStreet
+            | Round::SeventhStreet => min_bet * 4,
+            _ => min_bet * 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Betting::NoLimit => "nolimit",
+            Betting::PotLimit => "potlimit",
+            Betting::Limit => "limit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Betting> {
+        match name.to_lowercase().as_str() {
+            "nolimit" | "nl" => Some(Betting::NoLimit),
+            "potlimit" | "pl" => Some(Betting::PotLimit),
+            "fixedlimit" | "limit" | "fl" => Some(Betting::Limit),
+            _ => None,
+        }
+    }
+}
+
+// Chips that go in the pot before the cards are dealt, besides the blinds.
+// ante: paid by every player.
+// big_blind_ante: paid by the big blind alone for the whole table, after their blind.
+// straddle: a voluntary blind of twice the big blind, only with 3 or more players.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ForcedBets {
+    pub ante: u32,
+    pub big_blind_ante: u32,
+    pub straddle: Option<Straddle>,
+}
+
+// Who straddles. The straddler acts last preflop and keeps the option to raise,
+// so with a button straddle the small blind acts first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Straddle {
+    UnderTheGun,
+    Button,
+}
+
+impl Straddle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Straddle::UnderTheGun => "utg",
+            Straddle::Button => "button",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Straddle> {
+        match name.to_lowercase().as_str() {
+            "utg" | "underthegun" => Some(Straddle::UnderTheGun),
+            "button" | "btn" => Some(Straddle::Button),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,383 @@
+// equity.rs
+use crate::hands::*;
+use crate::poker::*;
+use crate::range::*;
+use crate::variant::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::cmp::Ordering::Equal;
+
+// Known cards to calculate equity for.
+// Runouts are enumerated when there are at most max_runouts of them,
+// otherwise `samples` random runouts are dealt from `seed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquityQuery {
+    pub holes: Vec<Vec<Card>>,
+    pub board: Vec<Card>,
+    pub dead: Vec<Card>,
+    pub variant: Variant,
+    pub max_runouts: u64,
+    pub samples: u64,
+    pub seed: u64,
+}
+
+// Percentages for one player
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerEquity {
+    // won alone
+    pub win: f64,
+    // split with someone else
+    pub tie: f64,
+    // share of the pot, ties count as a fraction of a win
+    pub equity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equity {
+    pub players: Vec<PlayerEquity>,
+    pub runouts: u64,
+    pub exhaustive: bool,
+}
+
+impl EquityQuery {
+    pub fn new(holes: Vec<Vec<Card>>) -> EquityQuery {
+        EquityQuery {
+            holes,
+            board: Vec::new(),
+            dead: Vec::new(),
+            variant: Variant::Holdem,
+            max_runouts: 100_000,
+            samples: 100_000,
+            seed: 0,
+        }
+    }
+
+    pub fn calculate(&self) -> Result<Equity, String> {
+        let deck = self.remaining_deck()?;
+        let missing = self.variant.table_size() - self.board.len();
+        let runouts = combinations(deck.len() as u64, missing as u64);
+
+        let holes: Vec<&[Card]> = self.holes.iter().map(|h| h.as_slice()).collect();
+        let mut tally = Tally::new(holes.len());
+        let mut board = self.board.clone();
+        let exhaustive = runouts <= self.max_runouts;
+        if exhaustive {
+            for_each_combination(deck.len(), missing, |indexes| {
+                board.truncate(self.board.len());
+                board.extend(indexes.iter().map(|&i| deck[i]));
+                tally.add(&winners(self.variant, &holes, &board), 1.0);
+            });
+        } else {
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            let mut deck = deck;
+            for _ in 0..self.samples {
+                let (runout, _) = deck.partial_shuffle(&mut rng, missing);
+                board.truncate(self.board.len());
+                board.extend_from_slice(runout);
+                tally.add(&winners(self.variant, &holes, &board), 1.0);
+            }
+        }
+        Ok(tally.finish(exhaustive))
+    }
+
+    // Deck without the known cards, checking they make sense
+    fn remaining_deck(&self) -> Result<Vec<Card>, String> {
+        check_game(self.variant, self.holes.len(), &self.board)?;
+        for hole in self.holes.iter() {
+            if hole.len() != self.variant.hole_size() {
+                return Err(format!(
+                    "Each player needs {} hole cards",
+                    self.variant.hole_size()
+                ));
+            }
+        }
+        let known: Vec<Card> = self
+            .holes
+            .iter()
+            .flatten()
+            .chain(self.board.iter())
+            .chain(self.dead.iter())
+            .copied()
+            .collect();
+        let deck = deck_without(self.variant, &known)?;
+        if deck.len() < self.variant.table_size() - self.board.len() {
+            return Err("Not enough cards left to finish the board".to_string());
+        }
+        Ok(deck)
+    }
+}
+
+// Same as EquityQuery, with a range of hole cards for each player.
+// Only for games with 2 hole cards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeEquityQuery {
+    pub ranges: Vec<Range>,
+    pub board: Vec<Card>,
+    pub dead: Vec<Card>,
+    pub variant: Variant,
+    // counting every combo of hole cards times every runout
+    pub max_runouts: u64,
+    pub samples: u64,
+    pub seed: u64,
+}
+
+impl RangeEquityQuery {
+    pub fn new(ranges: Vec<Range>) -> RangeEquityQuery {
+        RangeEquityQuery {
+            ranges,
+            board: Vec::new(),
+            dead: Vec::new(),
+            variant: Variant::Holdem,
+            max_runouts: 100_000,
+            samples: 100_000,
+            seed: 0,
+        }
+    }
+
+    pub fn calculate(&self) -> Result<Equity, String> {
+        check_game(self.variant, self.ranges.len(), &self.board)?;
+        if self.variant.hole_size() != 2 {
+            return Err(format!("No ranges in {}", self.variant.name()));
+        }
+        let known: Vec<Card> = self.board.iter().chain(self.dead.iter()).copied().collect();
+        let deck = deck_without(self.variant, &known)?;
+        // Card removal: combos using the board or dead cards can't be dealt
+        let mut combos = Vec::new();
+        for (i, range) in self.ranges.iter().enumerate() {
+            let mut left = range.without(&known);
+            left.retain(|c| c.cards.iter().all(|card| deck.contains(card)));
+            if left.is_empty() {
+                return Err(format!("No combos left in range {}", i + 1));
+            }
+            combos.push(left);
+        }
+
+        let missing = self.variant.table_size() - self.board.len();
+        let players = self.ranges.len();
+        if deck.len() < missing + 2 * players {
+            return Err("Not enough cards left to finish the board".to_string());
+        }
+        let runouts = combos.iter().fold(
+            combinations((deck.len() - 2 * players) as u64, missing as u64),
+            |total, c| total.saturating_mul(c.len() as u64),
+        );
+
+        let mut tally = Tally::new(players);
+        let exhaustive = runouts <= self.max_runouts;
+        if exhaustive {
+            let mut chosen = Vec::new();
+            self.enumerate(&combos, &deck, &mut chosen, &mut tally);
+        } else {
+            self.sample(&combos, deck, &mut tally)?;
+        }
+        if tally.runouts == 0 {
+            return Err("The ranges can't be dealt together".to_string());
+        }
+        Ok(tally.finish(exhaustive))
+    }
+
+    // Every runout for every combo of hole cards that can be dealt together
+    fn enumerate(
+        &self,
+        combos: &[Vec<Combo>],
+        deck: &[Card],
+        chosen: &mut Vec<Combo>,
+        tally: &mut Tally,
+    ) {
+        if chosen.len() < combos.len() {
+            for combo in combos[chosen.len()].iter() {
+                let taken: Vec<Card> = chosen.iter().flat_map(|c| c.cards).collect();
+                if combo.uses_any(&taken) {
+                    continue;
+                }
+                chosen.push(*combo);
+                self.enumerate(combos, deck, chosen, tally);
+                chosen.pop();
+            }
+            return;
+        }
+        let weight: f64 = chosen.iter().map(|c| c.weight).product();
+        let holes: Vec<&[Card]> = chosen.iter().map(|c| c.cards.as_slice()).collect();
+        let deck: Vec<Card> = deck
+            .iter()
+            .filter(|card| !chosen.iter().any(|c| c.cards.contains(card)))
+            .copied()
+            .collect();
+        let missing = self.variant.table_size() - self.board.len();
+        let mut board = self.board.clone();
+        for_each_combination(deck.len(), missing, |indexes| {
+            board.truncate(self.board.len());
+            board.extend(indexes.iter().map(|&i| deck[i]));
+            tally.add(&winners(self.variant, &holes, &board), weight);
+        });
+    }
+
+    // Random hole cards by weight, dealing again when two players share a card
+    fn sample(
+        &self,
+        combos: &[Vec<Combo>],
+        mut deck: Vec<Card>,
+        tally: &mut Tally,
+    ) -> Result<(), String> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let missing = self.variant.table_size() - self.board.len();
+        let mut board = self.board.clone();
+        for _ in 0..self.samples {
+            let mut attempts = 0;
+            let chosen = loop {
+                let chosen: Vec<Combo> = combos
+                    .iter()
+                    .map(|c| *c.choose_weighted(&mut rng, |c| c.weight).unwrap())
+                    .collect();
+                let cards: Vec<Card> = chosen.iter().flat_map(|c| c.cards).collect();
+                if (0..cards.len()).all(|i| !cards[..i].contains(&cards[i])) {
+                    break chosen;
+                }
+                attempts += 1;
+                if attempts == 10_000 {
+                    return Err("The ranges can't be dealt together".to_string());
+                }
+            };
+            let holes: Vec<&[Card]> = chosen.iter().map(|c| c.cards.as_slice()).collect();
+            // enough cards to skip the ones in the players' hands
+            let (shuffled, _) = deck.partial_shuffle(&mut rng, missing + 2 * combos.len());
+            board.truncate(self.board.len());
+            board.extend(
+                shuffled
+                    .iter()
+                    .filter(|card| !chosen.iter().any(|c| c.cards.contains(card)))
+                    .take(missing),
+            );
+            tally.add(&winners(self.variant, &holes, &board), 1.0);
+        }
+        Ok(())
+    }
+}
+
+// Checks the variant has a board and it isn't too big
+fn check_game(variant: Variant, players: usize, board: &[Card]) -> Result<(), String> {
+    if players < 2 {
+        return Err("Equity needs at least 2 players".to_string());
+    }
+    if variant.table_size() == 0 {
+        return Err(format!("No community cards in {}", variant.name()));
+    }
+    if board.len() > variant.table_size() {
+        return Err(format!(
+            "The board has at most {} cards",
+            variant.table_size()
+        ));
+    }
+    Ok(())
+}
+
+// The variant's deck without the known cards, which must all be different
+fn deck_without(variant: Variant, known: &[Card]) -> Result<Vec<Card>, String> {
+    for (i, card) in known.iter().enumerate() {
+        if !variant.ranks().contains(&card.rank) {
+            return Err(format!("{} isn't in the deck", card));
+        }
+        if known[..i].contains(card) {
+            return Err(format!("{} is used twice", card));
+        }
+    }
+    Ok(SUITS
+        .iter()
+        .flat_map(|&suit| variant.ranks().iter().map(move |&rank| Card { rank, suit }))
+        .filter(|card| !known.contains(card))
+        .collect())
+}
+
+// Players with the best hand on a complete board
+pub fn winners(variant: Variant, holes: &[&[Card]], board: &[Card]) -> Vec<usize> {
+    let hands: Vec<Hand> = holes.iter().map(|hole| variant.hand(hole, board)).collect();
+    let best = hands
+        .iter()
+        .max_by(|a, b| variant.compare_hands(a, b))
+        .unwrap();
+    (0..hands.len())
+        .filter(|&i| variant.compare_hands(&hands[i], best) == Equal)
+        .collect()
+}
+
+// Weighted results of the runouts dealt so far
+struct Tally {
+    wins: Vec<f64>,
+    ties: Vec<f64>,
+    shares: Vec<f64>,
+    weight: f64,
+    runouts: u64,
+}
+
+impl Tally {
+    fn new(players: usize) -> Tally {
+        Tally {
+            wins: vec![0.0; players],
+            ties: vec![0.0; players],
+            shares: vec![0.0; players],
+            weight: 0.0,
+            runouts: 0,
+        }
+    }
+
+    fn add(&mut self, winners: &[usize], weight: f64) {
+        for &winner in winners.iter() {
+            if winners.len() == 1 {
+                self.wins[winner] += weight;
+            } else {
+                self.ties[winner] += weight;
+            }
+            self.shares[winner] += weight / winners.len() as f64;
+        }
+        self.weight += weight;
+        self.runouts += 1;
+    }
+
+    fn finish(self, exhaustive: bool) -> Equity {
+        let percent = |count: f64| 100.0 * count / self.weight;
+        let players = (0..self.wins.len())
+            .map(|i| PlayerEquity {
+                win: percent(self.wins[i]),
+                tie: percent(self.ties[i]),
+                equity: percent(self.shares[i]),
+            })
+            .collect();
+        Equity {
+            players,
+            runouts: self.runouts,
+            exhaustive,
+        }
+    }
+}
+
+// Calls f with the indexes of every k out of n items, in order
+fn for_each_combination(n: usize, k: usize, mut f: impl FnMut(&[usize])) {
+    if k > n {
+        return;
+    }
+    let mut indexes: Vec<usize> = (0..k).collect();
+    loop {
+        f(&indexes);
+        // find the rightmost index that can still move up
+        let mut i = k;
+        while i > 0 && indexes[i - 1] == n - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return;
+        }
+        indexes[i - 1] += 1;
+        for j in i..k {
+            indexes[j] = indexes[j - 1] + 1;
+        }
+    }
+}
+
+// Ways to pick k out of n
+pub fn combinations(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |total, i| total * (n - i) / (i + 1))
+}
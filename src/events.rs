@@ -0,0 +1,235 @@
+// events.rs
+use crate::hands::*;
+use crate::playerinput::Action;
+use crate::poker::*;
+use crate::variant::*;
+use std::cmp::Ordering::Equal;
+
+// Everything that happens during a game, in order.
+// Players are referred to by seat index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    // Name and balance of every seat, before blinds are posted.
+    // seed: replays this hand with Game::with_hand_seed
+    HandStarted {
+        button: usize,
+        seed: u64,
+        seats: Vec<(String, u32)>,
+        variant: Variant,
+    },
+    HoleCardsDealt {
+        seat: usize,
+        cards: Vec<Card>,
+    },
+    // Face-up card, only in stud
+    UpCardDealt {
+        seat: usize,
+        card: Card,
+    },
+    AntePosted {
+        seat: usize,
+        amount: u32,
+    },
+    BlindPosted {
+        seat: usize,
+        amount: u32,
+    },
+    // Voluntary blind of twice the big blind
+    StraddlePosted {
+        seat: usize,
+        amount: u32,
+    },
+    BringInPosted {
+        seat: usize,
+        amount: u32,
+    },
+    TurnStarted {
+        seat: usize,
+    },
+    // amount: chips the action put in the pot
+    ActionTaken {
+        seat: usize,
+        action: Action,
+        amount: u32,
+    },
+    // cards: community cards revealed for the new street
+    StreetAdvanced {
+        round: Round,
+        cards: Vec<Card>,
+    },
+    // The rest of the board dealt again when running it more than once.
+    // run: 2 for the second run, and so on
+    BoardRun {
+        run: usize,
+        cards: Vec<Card>,
+    },
+    // run: which run of the board the hands are on, 1 unless it was run more than once
+    // lows: qualifying low hands, only in hi-lo games
+    Showdown {
+        run: usize,
+        hands: Vec<(usize, BestHand)>,
+        lows: Vec<(usize, LowHand)>,
+    },
+    // Chips of the top bet nobody called, given back before the pots are raked
+    UncalledReturned {
+        seat: usize,
+        amount: u32,
+    },
+    // Taken from a pot before it's awarded, same pot numbers as PotAwarded
+    RakeTaken {
+        pot: usize,
+        amount: u32,
+    },
+    // pot: 0 for the main pot, side pots after that
+    PotAwarded {
+        pot: usize,
+        seat: usize,
+        amount: u32,
+    },
+    HandEnded,
+    // Game::history_dir is set but the hand couldn't be written there
+    HistoryNotSaved {
+        path: String,
+        error: String,
+    },
+}
+
+pub trait EventSink {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+// Prints the game to the terminal
+#[derive(Debug, Default)]
+pub struct CliRenderer {
+    names: Vec<String>,
+    variant: Variant,
+    // runs of the board this hand
+    runs: usize,
+}
+
+impl CliRenderer {
+    pub fn new() -> CliRenderer {
+        CliRenderer::default()
+    }
+
+    // Why the best hand beats the next best one
+    fn explain_showdown(&self, hands: &[(usize, BestHand)]) {
+        let compare = |a: &BestHand, b: &BestHand| self.variant.compare_hands(&a.hand, &b.hand);
+        let Some((seat, best)) = hands.iter().max_by(|a, b| compare(&a.1, &b.1)) else {
+            return;
+        };
+        let (tied, others): (Vec<_>, Vec<_>) =
+            hands.iter().partition(|(_, h)| compare(h, best) == Equal);
+        if tied.len() > 1 {
+            let names: Vec<&str> = tied.iter().map(|(s, _)| self.names[*s].as_str()).collect();
+            println!("{} tie with the same hand", names.join(" and "));
+        } else if let Some((_, next)) = others.iter().max_by(|a, b| compare(&a.1, &b.1)) {
+            println!(
+                "{} {}",
+                self.names[*seat],
+                best.hand.explain_win(&next.hand)
+            );
+        }
+    }
+}
+
+impl EventSink for CliRenderer {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::HandStarted {
+                button,
+                seed,
+                seats,
+                variant,
+            } => {
+                self.names = seats.iter().map(|(name, _)| name.clone()).collect();
+                self.variant = *variant;
+                self.runs = 1;
+                println!(
+                    "New hand (hand seed {}), {} has the button",
+                    seed, self.names[*button]
+                );
+            }
+            GameEvent::HoleCardsDealt { .. } => {}
+            GameEvent::UpCardDealt { seat, card } => {
+                println!("{} shows {:#}", self.names[*seat], card);
+            }
+            GameEvent::AntePosted { seat, amount } => {
+                println!("{} posts an ante of {}$", self.names[*seat], amount);
+            }
+            GameEvent::BlindPosted { seat, amount } => {
+                println!("{} posts a blind of {}$", self.names[*seat], amount);
+            }
+            GameEvent::StraddlePosted { seat, amount } => {
+                println!("{} straddles for {}$", self.names[*seat], amount);
+            }
+            GameEvent::BringInPosted { seat, amount } => {
+                println!("{} brings in for {}$", self.names[*seat], amount);
+            }
+            GameEvent::TurnStarted { seat } => println!("{}'s turn", self.names[*seat]),
+            GameEvent::ActionTaken {
+                seat,
+                action,
+                amount,
+            } => {
+                let name = &self.names[*seat];
+                match action {
+                    Action::Check => println!("{} checks", name),
+                    Action::Call => println!("{} calls {}$", name, amount),
+                    Action::Bet(amount) => println!("{} bets {}$", name, amount),
+                    Action::Raise(to) => println!("{} raises to {}$", name, to),
+                    Action::AllIn => println!("{} goes all-in with {}$", name, amount),
+                    Action::Fold => println!("{} folds", name),
+                }
+            }
+            GameEvent::StreetAdvanced { round, cards } => {
+                println!("{:?}:", round);
+                for card in cards {
+                    println!("{:#}", card);
+                }
+            }
+            GameEvent::BoardRun { run, cards } => {
+                self.runs = *run;
+                println!("Run {}:", run);
+                for card in cards {
+                    println!("{:#}", card);
+                }
+            }
+            GameEvent::Showdown { run, hands, lows } => {
+                if self.runs > 1 {
+                    println!("Run {}:", run);
+                }
+                for (seat, best) in hands {
+                    println!(
+                        "{}'s hand: {} ({:+})",
+                        self.names[*seat],
+                        best.hand,
+                        Cards(best.cards.to_vec())
+                    );
+                }
+                self.explain_showdown(hands);
+                for (seat, low) in lows {
+                    println!("{}'s low: {}", self.names[*seat], low);
+                }
+            }
+            GameEvent::UncalledReturned { seat, amount } => {
+                println!("{} takes back {}$ nobody called", self.names[*seat], amount);
+            }
+            GameEvent::RakeTaken { pot, amount } => {
+                let pot_name = if *pot == 0 { "main pot" } else { "side pot" };
+                println!("The house takes {}$ from the {}", amount, pot_name);
+            }
+            GameEvent::PotAwarded { pot, seat, amount } => {
+                let pot_name = if *pot == 0 { "main pot" } else { "side pot" };
+                println!(
+                    "{} won {}$ from the {}",
+                    self.names[*seat], amount, pot_name
+                );
+            }
+            GameEvent::HandEnded => println!("Game ended!"),
+            GameEvent::HistoryNotSaved { path, error } => {
+                eprintln!("Couldn't save hand history to {}: {}", path, error);
+            }
+        }
+    }
+}
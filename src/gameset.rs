@@ -1,66 +1,233 @@
 // gameset.rs
+use crate::betting::*;
+use crate::events::*;
 use crate::hands::*;
+use crate::playerinput::*;
 use crate::poker::*;
+use crate::rake::*;
+use crate::variant::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use std::cmp::Ordering::Equal;
+use std::path::PathBuf;
 
 const INITIAL_BALANCE: u32 = 500;
+const NO_RAISE: &str = "You can't raise now, call or fold!";
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Player {
     pub name: String,
+    // hole: face-down cards, upcards: face-up cards (only in stud)
     pub hole: Vec<Card>,
+    pub upcards: Vec<Card>,
+    // bet: chips put in during the current round
+    // total_bet: chips put in during the whole game, used to build side pots
     pub bet: u32,
+    pub total_bet: u32,
     pub is_playing: bool,
     pub folded: bool,
+    pub all_in: bool,
+    pub acted: bool,
     pub balance: u32,
+    pub controller: Box<dyn PlayerController>,
 }
 
 impl Player {
     pub fn new(name: String) -> Player {
+        Player::with_controller(name, Box::new(StdinController { hints: false }))
+    }
+
+    pub fn with_controller(name: String, controller: Box<dyn PlayerController>) -> Player {
         Player {
             name,
             hole: Vec::new(),
+            upcards: Vec::new(),
             bet: 0,
+            total_bet: 0,
             is_playing: true,
             folded: false,
+            all_in: false,
+            acted: false,
             balance: INITIAL_BALANCE,
+            controller,
         }
     }
 
-    pub fn get_hand(&self, game: &Game) -> Hand {
-        let cards: Vec<Card> = self
-            .hole
+    pub fn get_hand(&self, game: &Game) -> BestHand {
+        self.get_hand_on(game, &game.table)
+    }
+
+    // Best hand with the given board, e.g. another run of it
+    pub fn get_hand_on(&self, game: &Game, board: &[Card]) -> BestHand {
+        let config = &game.config;
+        if config.has_wild_cards() {
+            return config
+                .variant
+                .wild_best_hand(&self.cards(), board, &config.wild);
+        }
+        config.variant.best_hand(&self.cards(), board)
+    }
+
+    // Own cards, face-down then face-up
+    pub fn cards(&self) -> Vec<Card> {
+        self.hole
             .iter()
-            .chain(game.table.iter())
+            .chain(self.upcards.iter())
             .copied()
-            .collect::<Vec<_>>();
-        calculate_hand(cards.as_slice())
+            .collect()
     }
 
     pub fn reset(&mut self) {
         self.hole = Vec::new();
+        self.upcards = Vec::new();
         self.bet = 0;
+        self.total_bet = 0;
         self.folded = false;
+        self.all_in = false;
+        self.acted = false;
+    }
+
+    // Still fighting for the pot
+    pub fn in_hand(&self) -> bool {
+        self.is_playing && !self.folded
+    }
+
+    // Still able to make decisions
+    pub fn can_act(&self) -> bool {
+        self.in_hand() && !self.all_in
+    }
+
+    // Moves chips from the player's balance to the current bet.
+    // Returns the amount actually moved.
+    fn put_in(&mut self, amount: u32) -> u32 {
+        let amount = amount.min(self.balance);
+        self.balance -= amount;
+        self.bet += amount;
+        self.total_bet += amount;
+        if self.balance == 0 {
+            self.all_in = true;
+        }
+        amount
+    }
+
+    // Antes go in the pot without counting towards the round's bet
+    fn put_in_ante(&mut self, amount: u32) -> u32 {
+        let amount = self.put_in(amount);
+        self.bet -= amount;
+        amount
     }
 }
 
+// Hands and qualifying lows by seat on one run of the board
+type RunHands = (Vec<Option<BestHand>>, Vec<Option<LowHand>>);
+
+// A pot and the players (by seat index) who can win it.
+// The first pot is the main pot, the rest are side pots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pot {
+    pub amount: u32,
+    pub eligible: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameConfig {
+    // small blind, the big blind is twice this.
+    // In stud there are no blinds and this is the bring-in.
+    pub min_bet: u32,
+    // antes, big blind ante and straddle, paid before the cards are dealt
+    pub forced_bets: ForcedBets,
+    pub variant: Variant,
+    pub betting: Betting,
+    // jokers added to the deck, up to 2
+    pub jokers: usize,
+    // ranks that are wild like jokers, e.g. Two for deuces wild
+    pub wild: Vec<Rank>,
+    // the house's cut, none by default
+    pub rake: Option<Rake>,
+    // times the rest of the board is dealt when everyone is all-in before the river
+    pub runs: usize,
+}
+
+impl GameConfig {
+    pub fn new(min_bet: u32) -> GameConfig {
+        GameConfig {
+            min_bet,
+            forced_bets: ForcedBets::default(),
+            variant: Variant::Holdem,
+            betting: Betting::NoLimit,
+            jokers: 0,
+            wild: Vec::new(),
+            rake: None,
+            runs: 1,
+        }
+    }
+
+    pub fn has_wild_cards(&self) -> bool {
+        self.jokers > 0 || !self.wild.is_empty()
+    }
+
+    // Settings the engine can't play
+    pub fn validate(&self) -> Result<(), String> {
+        if self.jokers > JOKERS.len() {
+            return Err(format!("At most {} jokers", JOKERS.len()));
+        }
+        // Low hands are only evaluated without wild cards
+        if self.has_wild_cards() && self.variant.is_hi_lo() {
+            return Err("No wild cards in hi-lo games".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Cards to deal instead of shuffled ones, for replays.
+// holes and upcards are by seat, cards that aren't given come from the deck.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DealtCards {
+    pub holes: Vec<Vec<Card>>,
+    pub upcards: Vec<Vec<Card>>,
+    pub board: Vec<Card>,
+}
+
 pub struct Game {
     pub players: Vec<Player>,
+    // community cards dealt so far
     pub table: Vec<Card>,
+    // cards of each extra run of the board, replacing the end of `table`
+    pub extra_runs: Vec<Vec<Card>>,
     pub deck: Vec<Card>,
     pub turn: (Round, usize),
     pub pot: u32,
+    // rake taken from this hand's pots
+    pub rake: u32,
+    // chips the players had when the hand started, see check_ledger
+    pub chips: u32,
     pub bet: u32,
+    // size of the last full raise on this street, raises must be at least this big
+    pub last_raise: u32,
+    // bets and raises on this street, capped in fixed limit
+    pub raises: u32,
+    pub config: GameConfig,
+    pub button: usize,
     pub ended: bool,
-    last: Option<usize>,
-    looped: bool,
+    // seed: the game's RNG seed, if it was built from one
+    // hand_seed: the deck of the current hand is shuffled with this seed
+    pub seed: Option<u64>,
+    pub hand_seed: u64,
+    rng: Box<dyn RngCore>,
+    stacked: Option<DealtCards>,
+    // Every finished hand's history is saved here, if set
+    pub history_dir: Option<PathBuf>,
+    // Events of the current hand
+    pub events: Vec<GameEvent>,
+    sinks: Vec<Box<dyn EventSink>>,
 }
 
 impl Game {
     fn setup_deck(&mut self) {
         self.deck = Vec::new();
 
-        for rank in RANKS.iter() {
+        for rank in self.config.variant.ranks().iter() {
             for suit in SUITS.iter() {
                 self.deck.push(Card {
                     rank: *rank,
@@ -68,223 +235,1022 @@ impl Game {
                 });
             }
         }
+        self.deck.extend_from_slice(&JOKERS[..self.config.jokers]);
 
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(self.hand_seed);
         self.deck.shuffle(&mut rng);
+
+        // Stacked cards can't also be in the deck
+        if let Some(stacked) = &self.stacked {
+            let known: Vec<&Card> = stacked
+                .holes
+                .iter()
+                .chain(stacked.upcards.iter())
+                .flatten()
+                .chain(stacked.board.iter())
+                .collect();
+            self.deck.retain(|c| !known.contains(&c));
+        }
+    }
+
+    // Next card for the given seat (face-down or face-up), or for the board.
+    // Takes the stacked card if there is one.
+    fn draw(&mut self, seat: Option<usize>, face_up: bool) -> Card {
+        if let Some(stacked) = &mut self.stacked {
+            let cards = match seat {
+                Some(seat) if face_up => stacked.upcards.get_mut(seat),
+                Some(seat) => stacked.holes.get_mut(seat),
+                None => Some(&mut stacked.board),
+            };
+            if let Some(cards) = cards.filter(|c| !c.is_empty()) {
+                return cards.remove(0);
+            }
+        }
+        self.deck.pop().unwrap()
     }
 
     fn deal(&mut self) {
-        for player in self.players.iter_mut() {
-            for _ in 0..HOLE_SIZE {
-                player.hole.push(self.deck.pop().unwrap());
+        if self.config.variant.is_stud() {
+            self.deal_stud(HOLE_SIZE, 1);
+            return;
+        }
+        for seat in 0..self.players.len() {
+            if !self.players[seat].is_playing {
+                continue;
             }
+            for _ in 0..self.config.variant.hole_size() {
+                let card = self.draw(Some(seat), false);
+                self.players[seat].hole.push(card);
+            }
+            let cards = self.players[seat].hole.clone();
+            self.emit(GameEvent::HoleCardsDealt { seat, cards });
         }
-        for _ in 0..TABLE_SIZE {
-            self.table.push(self.deck.pop().unwrap());
+    }
+
+    // Deals the community cards of a new street
+    fn deal_board(&mut self) -> Vec<Card> {
+        let size = match self.turn.0 {
+            Round::Flop => 3,
+            Round::Turn => 4,
+            Round::River => self.config.variant.table_size(),
+            _ => 0,
+        };
+        let cards: Vec<Card> = (self.table.len()..size)
+            .map(|_| self.draw(None, false))
+            .collect();
+        self.table.extend(cards.iter());
+        cards
+    }
+
+    // Deals stud cards to everyone still in the hand, starting left of the button
+    fn deal_stud(&mut self, down: usize, up: usize) {
+        let len = self.players.len();
+        for i in 1..=len {
+            let seat = (self.button + i) % len;
+            if !self.players[seat].in_hand() {
+                continue;
+            }
+            if down > 0 {
+                let cards: Vec<Card> = (0..down).map(|_| self.draw(Some(seat), false)).collect();
+                self.players[seat].hole.extend(cards.iter());
+                self.emit(GameEvent::HoleCardsDealt { seat, cards });
+            }
+            for _ in 0..up {
+                let card = self.draw(Some(seat), true);
+                self.players[seat].upcards.push(card);
+                self.emit(GameEvent::UpCardDealt { seat, card });
+            }
         }
     }
 
-    pub fn new(mut players: Vec<Player>, min_bet: u32, is_first_game: bool) -> Game {
-        if is_first_game {
-            let mut rng = rand::thread_rng();
-            players.shuffle(&mut rng);
+    // Sends all events of the current hand so far, then every new one
+    pub fn subscribe(&mut self, mut sink: Box<dyn EventSink>) {
+        for event in self.events.iter() {
+            sink.on_event(event);
         }
-        for player in players.iter_mut() {
-            if player.balance < min_bet * 2 {
-                player.is_playing = false;
-            }
+        self.sinks.push(sink);
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        for sink in self.sinks.iter_mut() {
+            sink.on_event(&event);
+        }
+        self.events.push(event);
+    }
+
+    pub fn new(players: Vec<Player>, min_bet: u32, is_first_game: bool) -> Game {
+        Game::with_config(players, GameConfig::new(min_bet), is_first_game)
+    }
+
+    pub fn with_config(players: Vec<Player>, config: GameConfig, is_first_game: bool) -> Game {
+        let seed = rand::thread_rng().gen();
+        Game::with_seed(players, config, is_first_game, seed)
+    }
+
+    // The same seed and players always play out the same seats and cards
+    pub fn with_seed(
+        players: Vec<Player>,
+        config: GameConfig,
+        is_first_game: bool,
+        seed: u64,
+    ) -> Game {
+        let mut game = Game::build(players, config, Box::new(StdRng::seed_from_u64(seed)));
+        game.seed = Some(seed);
+        game.start(is_first_game);
+        game
+    }
+
+    pub fn with_rng(
+        players: Vec<Player>,
+        config: GameConfig,
+        is_first_game: bool,
+        rng: Box<dyn RngCore>,
+    ) -> Game {
+        let mut game = Game::build(players, config, rng);
+        game.start(is_first_game);
+        game
+    }
+
+    // Deals the exact hand shown with a seed in HandStarted, given the same
+    // players in the same seats and the same button
+    pub fn with_hand_seed(
+        players: Vec<Player>,
+        config: GameConfig,
+        button: usize,
+        hand_seed: u64,
+    ) -> Game {
+        let mut game = Game::build(players, config, Box::new(StdRng::seed_from_u64(hand_seed)));
+        game.seat_players();
+        game.button = button;
+        game.hand_seed = hand_seed;
+        game.start_hand();
+        game
+    }
+
+    // Deals the given cards instead of shuffled ones
+    pub fn with_cards(
+        players: Vec<Player>,
+        config: GameConfig,
+        button: usize,
+        hand_seed: u64,
+        cards: DealtCards,
+    ) -> Game {
+        let mut game = Game::build(players, config, Box::new(StdRng::seed_from_u64(hand_seed)));
+        game.seat_players();
+        game.button = button;
+        game.hand_seed = hand_seed;
+        game.stacked = Some(cards);
+        game.start_hand();
+        game
+    }
+
+    fn build(players: Vec<Player>, config: GameConfig, rng: Box<dyn RngCore>) -> Game {
+        if let Err(error) = config.validate() {
+            panic!("{}!", error);
         }
-        let mut new_game = Game {
+        Game {
             players,
             table: Vec::new(),
+            extra_runs: Vec::new(),
             deck: Vec::new(),
-            turn: (Round::PreFlop, 0),
+            turn: (config.variant.first_round(), 0),
             pot: 0,
-            bet: min_bet * 2,
+            rake: 0,
+            chips: 0,
+            bet: 0,
+            last_raise: 0,
+            raises: 0,
+            config,
+            button: 0,
             ended: false,
-            last: None,
-            looped: true,
+            seed: None,
+            hand_seed: 0,
+            rng,
+            stacked: None,
+            history_dir: None,
+            events: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    fn start(&mut self, is_first_game: bool) {
+        if is_first_game {
+            self.players.shuffle(&mut self.rng);
+        }
+        self.seat_players();
+        if !self.players[0].is_playing {
+            self.button = self.next_playing(0);
+        }
+        self.hand_seed = self.rng.next_u64();
+        self.start_hand();
+    }
+
+    // Moves the button to the next player with chips and plays another hand
+    // with the same players.
+    pub fn new_hand(&mut self) {
+        for player in self.players.iter_mut() {
+            player.reset();
+        }
+        self.table = Vec::new();
+        self.extra_runs = Vec::new();
+        self.turn = (self.config.variant.first_round(), 0);
+        self.stacked = None;
+        self.pot = 0;
+        self.rake = 0;
+        self.bet = 0;
+        self.ended = false;
+        self.events = Vec::new();
+        self.seat_players();
+        self.button = self.next_playing(self.button);
+        self.hand_seed = self.rng.next_u64();
+        self.start_hand();
+    }
+
+    // Busted players sit out
+    fn seat_players(&mut self) {
+        for player in self.players.iter_mut() {
+            player.is_playing = player.balance > 0;
+        }
+        let playing = self.players.iter().filter(|p| p.is_playing).count();
+        if playing < 2 {
+            panic!("Not enough players with chips to start a game!");
+        }
+        if playing > self.config.variant.max_players() {
+            panic!("Too many players for {}!", self.config.variant.name());
+        }
+    }
+
+    fn start_hand(&mut self) {
+        self.chips = self.chip_total();
+        let seats = self
+            .players
+            .iter()
+            .map(|p| (p.name.clone(), p.balance))
+            .collect();
+        self.emit(GameEvent::HandStarted {
+            button: self.button,
+            seed: self.hand_seed,
+            seats,
+            variant: self.config.variant,
+        });
+        self.setup_deck();
+        if self.config.forced_bets.ante > 0 {
+            self.post_antes();
+        }
+        if self.config.variant.is_stud() {
+            self.deal();
+            self.post_bring_in();
+            return;
+        }
+        let (small_blind, big_blind) = self.blinds();
+        self.post_blind(small_blind, self.config.min_bet);
+        self.post_blind(big_blind, self.config.min_bet * 2);
+        self.bet = self.config.min_bet * 2;
+        self.last_raise = self.street_bet();
+        self.raises = 1;
+        // The blind goes first if the big blind can't pay both
+        let big_blind_ante = self.config.forced_bets.big_blind_ante;
+        if big_blind_ante > 0 {
+            self.post_ante(big_blind, big_blind_ante);
+        }
+        // Preflop action starts left of the big blind, or of the straddler
+        let mut last_blind = big_blind;
+        if let Some(seat) = self.straddler() {
+            self.post_straddle(seat);
+            last_blind = seat;
+        }
+        self.deal();
+        self.turn.1 = self.next_to_act(last_blind);
+    }
+
+    // Seat that straddles this hand, if the game has a straddle, there are 3 or
+    // more players and the seat can afford it without going all-in
+    pub fn straddler(&self) -> Option<usize> {
+        let straddle = self.config.forced_bets.straddle?;
+        let playing = self.players.iter().filter(|p| p.is_playing).count();
+        if playing < 3 {
+            return None;
+        }
+        let seat = match straddle {
+            Straddle::UnderTheGun => self.next_playing(self.blinds().1),
+            Straddle::Button => self.button,
         };
-        new_game.setup_deck();
-        new_game.deal();
-        new_game
+        (self.players[seat].balance > self.config.min_bet * 4).then_some(seat)
     }
 
-    fn advance(&mut self) {
-        // advance
-        let len = self.players.len();
-        self.turn.1 = (self.turn.1 + 1) % len;
-        // find last player
-        let mut last_index: i32 = -1;
-        if let Some(player) = self.last {
-            last_index = player as i32;
+    // A straddle is a live blind of twice the big blind, so raises over it
+    // must be at least its size
+    fn post_straddle(&mut self, seat: usize) {
+        let posted = self.players[seat].put_in(self.config.min_bet * 4);
+        self.pot += posted;
+        self.bet = posted;
+        self.last_raise = posted;
+        self.raises += 1;
+        self.emit(GameEvent::StraddlePosted {
+            seat,
+            amount: posted,
+        });
+    }
+
+    // Seats of the small and big blinds.
+    // Heads-up the button posts the small blind.
+    pub fn blinds(&self) -> (usize, usize) {
+        let playing = self.players.iter().filter(|p| p.is_playing).count();
+        let small_blind = if playing == 2 {
+            self.button
         } else {
-            for i in 0..len {
-                if self.players[i].is_playing {
-                    last_index = i as i32;
-                    break;
-                }
+            self.next_playing(self.button)
+        };
+        (small_blind, self.next_playing(small_blind))
+    }
+
+    // Blinds don't count as acting, so the big blind still gets the option to raise
+    fn post_blind(&mut self, seat: usize, amount: u32) {
+        let posted = self.players[seat].put_in(amount);
+        self.pot += posted;
+        self.emit(GameEvent::BlindPosted {
+            seat,
+            amount: posted,
+        });
+    }
+
+    fn post_antes(&mut self) {
+        for seat in 0..self.players.len() {
+            if self.players[seat].is_playing {
+                self.post_ante(seat, self.config.forced_bets.ante);
             }
         }
-        // No playing players left
-        if last_index == -1 {
+    }
+
+    fn post_ante(&mut self, seat: usize, amount: u32) {
+        let posted = self.players[seat].put_in_ante(amount);
+        self.pot += posted;
+        self.emit(GameEvent::AntePosted {
+            seat,
+            amount: posted,
+        });
+    }
+
+    // The lowest face-up card brings in, and action continues to their left.
+    // Unlike a blind, the bring-in counts as acting.
+    fn post_bring_in(&mut self) {
+        let seat = (0..self.players.len())
+            .filter(|&i| self.players[i].in_hand())
+            .min_by_key(|&i| crate::stud::bring_in_order(&self.players[i].upcards[0]))
+            .unwrap();
+        let posted = self.players[seat].put_in(self.config.min_bet);
+        self.pot += posted;
+        self.players[seat].acted = true;
+        self.bet = self.config.min_bet;
+        // Completing to a full bet is the first raise
+        self.last_raise = self.street_bet() - self.config.min_bet;
+        self.raises = 0;
+        self.emit(GameEvent::BringInPosted {
+            seat,
+            amount: posted,
+        });
+        self.turn.1 = self.next_to_act(seat);
+    }
+
+    // Who starts the betting after the first round
+    fn first_to_act(&self) -> usize {
+        if !self.config.variant.is_stud() {
+            // Postflop action starts left of the button
+            return self.next_to_act(self.button);
+        }
+        // Best face-up cards start, ties go to the first seat left of the button
+        let len = self.players.len();
+        let mut best: Option<usize> = None;
+        for i in 1..=len {
+            let seat = (self.button + i) % len;
+            if !self.players[seat].can_act() {
+                continue;
+            }
+            let visible = crate::stud::visible_hand(&self.players[seat].upcards);
+            if best.is_none_or(|b| visible > crate::stud::visible_hand(&self.players[b].upcards)) {
+                best = Some(seat);
+            }
+        }
+        best.unwrap_or(self.turn.1)
+    }
+
+    // First player dealt into the game after the given seat
+    fn next_playing(&self, seat: usize) -> usize {
+        let len = self.players.len();
+        (1..=len)
+            .map(|i| (seat + i) % len)
+            .find(|&i| self.players[i].is_playing)
+            .unwrap_or(seat)
+    }
+
+    // First player able to act after the given seat
+    fn next_to_act(&self, seat: usize) -> usize {
+        let len = self.players.len();
+        (1..=len)
+            .map(|i| (seat + i) % len)
+            .find(|&i| self.players[i].can_act())
+            .unwrap_or(seat)
+    }
+
+    fn round_complete(&self) -> bool {
+        self.players
+            .iter()
+            .filter(|p| p.can_act())
+            .all(|p| p.acted && p.bet == self.bet)
+    }
+
+    fn advance(&mut self) {
+        let in_hand = self.players.iter().filter(|p| p.in_hand()).count();
+        if in_hand == 0 {
             panic!("No players left!");
-        } else if self.turn.1 == last_index as usize {
-            // Round passes
-            if self.looped {
-                self.bet = 0;
-                self.looped = false;
-                self.turn.0.next();
-                // set players who folded to not playing
-                for player in self.players.iter_mut() {
-                    player.bet = 0;
-                    if player.folded {
-                        player.is_playing = false;
-                        player.folded = false;
-                    }
-                }
-                // Check if (self.turn.0 == Round::Showdown), or if there is only one player playing
-                if self.turn.0 == Round::Showdown
-                    || self
-                        .players
-                        .iter()
-                        .filter(|p| p.is_playing && !p.folded)
-                        .count()
-                        == 1
-                {
-                    self.end_game();
-                    return;
-                }
+        } else if in_hand == 1 {
+            self.end_game();
+            return;
+        }
+        if !self.round_complete() {
+            self.turn.1 = self.next_to_act(self.turn.1);
+            return;
+        }
+        // Round passes
+        self.bet = 0;
+        for player in self.players.iter_mut() {
+            player.bet = 0;
+            player.acted = false;
+        }
+        // Nobody left to bet against: run the board out
+        let can_act = self.players.iter().filter(|p| p.can_act()).count();
+        if can_act < 2 {
+            self.run_out();
+            self.end_game();
+            return;
+        }
+        self.next_street();
+        if self.turn.0 == Round::Showdown {
+            self.end_game();
+            return;
+        }
+        self.last_raise = self.street_bet();
+        self.raises = 0;
+        self.turn.1 = self.first_to_act();
+    }
+
+    // Deals every street left. If cards were still to come, the rest of the board
+    // is dealt again from the same deck for each extra run in the config, as long
+    // as there are cards left for it.
+    fn run_out(&mut self) {
+        let shown = self.table.len();
+        while self.turn.0 != Round::Showdown {
+            self.next_street();
+        }
+        let needed = self.table.len() - shown;
+        if needed == 0 {
+            return;
+        }
+        for run in 2..=self.config.runs {
+            let stacked = self.stacked.as_ref().map_or(0, |s| s.board.len());
+            if self.deck.len() + stacked < needed {
+                break;
             }
-            self.looped = true;
+            let cards: Vec<Card> = (shown..self.table.len())
+                .map(|_| self.draw(None, false))
+                .collect();
+            self.extra_runs.push(cards.clone());
+            self.emit(GameEvent::BoardRun { run, cards });
         }
     }
 
-    pub fn play_turn(&mut self) {
-        use crate::playerinput::*;
+    // Every board the hand is played on: the table, then each extra run
+    pub fn boards(&self) -> Vec<Vec<Card>> {
+        let mut boards = vec![self.table.clone()];
+        for run in self.extra_runs.iter() {
+            let shown = self.table.len() - run.len();
+            boards.push([&self.table[..shown], run].concat());
+        }
+        boards
+    }
 
-        let current_player = &mut self.players[self.turn.1];
-        if current_player.folded || !current_player.is_playing {
-            println!("{} is out of the game! Turn skipped", current_player.name);
+    fn next_street(&mut self) {
+        self.turn.0.next();
+        if self.turn.0 == Round::Showdown {
+            return;
+        }
+        let cards = self.deal_board();
+        self.emit(GameEvent::StreetAdvanced {
+            round: self.turn.0,
+            cards,
+        });
+        match self.turn.0 {
+            Round::FourthStreet | Round::FifthStreet | Round::SixthStreet => self.deal_stud(0, 1),
+            Round::SeventhStreet => self.deal_stud(1, 0),
+            _ => {}
+        }
+    }
+
+    pub fn play_turn(&mut self) {
+        let seat = self.turn.1;
+        if !self.players[seat].can_act() {
             self.advance();
             return;
         }
         // Player main loop
-        println!("{}'s turn", current_player.name);
+        self.emit(GameEvent::TurnStarted { seat });
+        let view = self.view(seat);
         loop {
-            if let Ok(action) = get_action() {
-                match action {
-                    Action::Check => {
-                        if current_player.bet < self.bet {
-                            println!("Can't check! Current bet is {}$", self.bet);
-                            continue;
-                        }
-                        break;
-                    }
-                    Action::Raise(amount) => {
-                        let difference = amount - current_player.bet;
-                        
-                        if amount <= self.bet {
-                            println!("Must raise higher than the current bet! {}$", self.bet);
-                            continue;
-                        }
-                        if difference > current_player.balance {
-                            println!(
-                                "You don't have enough money! {}$ remaining",
-                                current_player.balance
-                            );
-                            continue;
-                        }
-                        self.bet = amount;
-                        self.last = Some(self.turn.1);
-                        // Calculate pot and player balance
-                        current_player.bet = amount;
-                        current_player.balance -= difference;
-                        self.pot += difference;
-                        break;
+            let action = self.players[seat].controller.act(&view);
+            match self.take_action(action) {
+                Ok(()) => break,
+                Err(message) => self.players[seat].controller.illegal_action(&message),
+            }
+        }
+    }
+
+    // Plays turns until the game ends
+    pub fn play(&mut self) {
+        while !self.ended {
+            self.play_turn();
+        }
+    }
+
+    // What the given seat is allowed to see
+    pub fn view(&self, seat: usize) -> GameView {
+        let player = &self.players[seat];
+        GameView {
+            seat,
+            variant: self.config.variant,
+            wild: self.config.has_wild_cards(),
+            raise_range: self.raise_range(seat),
+            hole: player.hole.clone(),
+            table: self.board().to_vec(),
+            round: self.turn.0,
+            pot: self.pot,
+            bet: self.bet,
+            player_bet: player.bet,
+            balance: player.balance,
+            button: self.button,
+            seats: self
+                .players
+                .iter()
+                .map(|p| SeatView {
+                    name: p.name.clone(),
+                    upcards: p.upcards.clone(),
+                    balance: p.balance,
+                    bet: p.bet,
+                    folded: p.folded || !p.is_playing,
+                    all_in: p.all_in,
+                })
+                .collect(),
+        }
+    }
+
+    // Community cards already shown
+    pub fn board(&self) -> &[Card] {
+        &self.table
+    }
+
+    // Plays an action for the current player and moves on to the next turn.
+    // Invalid actions leave the game untouched.
+    pub fn take_action(&mut self, action: Action) -> Result<(), String> {
+        self.apply_action(action)?;
+        self.advance();
+        Ok(())
+    }
+
+    fn apply_action(&mut self, action: Action) -> Result<(), String> {
+        let seat = self.turn.1;
+        let pot_before = self.pot;
+        let current_player = &mut self.players[seat];
+        match action {
+            Action::Check => {
+                if current_player.bet < self.bet {
+                    return Err(format!("Can't check! Current bet is {}$", self.bet));
+                }
+            }
+            // A raise on a street nobody has bet on yet is a bet, like in older histories
+            Action::Bet(amount) | Action::Raise(amount) => {
+                if matches!(action, Action::Bet(_)) && self.bet > 0 {
+                    return Err(format!(
+                        "There's already a bet of {}$, raise instead",
+                        self.bet
+                    ));
+                }
+                if amount <= self.bet {
+                    return Err(format!(
+                        "Must raise higher than the current bet! {}$",
+                        self.bet
+                    ));
+                }
+                let difference = amount - current_player.bet;
+                if difference > current_player.balance {
+                    return Err(format!(
+                        "You don't have enough money! {}$ remaining, go all-in instead",
+                        current_player.balance
+                    ));
+                }
+                match self.raise_range(seat) {
+                    None => return Err(NO_RAISE.to_string()),
+                    Some((min, max)) if amount < min || amount > max => {
+                        return Err(format!("Must raise to between {}$ and {}$!", min, max));
                     }
-                    Action::Call => {
-                        if current_player.balance < self.bet - current_player.bet {
-                            println!(
-                                "You don't have enough money! {}$ remaining",
-                                current_player.balance
-                            );
-                            continue;
+                    _ => {}
+                }
+                let current_player = &mut self.players[seat];
+                self.pot += current_player.put_in(difference);
+                self.raise_to(amount);
+            }
+            Action::Call => {
+                // Calling with less than the bet puts the player all-in
+                let difference = self.bet.saturating_sub(current_player.bet);
+                self.pot += current_player.put_in(difference);
+            }
+            Action::AllIn => {
+                if current_player.balance == 0 {
+                    return Err("You have no money left to bet!".to_string());
+                }
+                let stack = current_player.bet + current_player.balance;
+                if stack > self.bet {
+                    match self.raise_range(seat) {
+                        None => return Err(NO_RAISE.to_string()),
+                        Some((_, max)) if stack > max => {
+                            return Err(format!(
+                                "Can't go all-in for {}$, the most you can raise to is {}$",
+                                stack, max
+                            ));
                         }
-                        // Calculate player balance
-                        let difference = self.bet - current_player.bet;
-                        current_player.bet = self.bet;
-                        current_player.balance -= difference;
-                        self.pot += difference;
-                        break;
-                    }
-                    Action::Fold => {
-                        current_player.folded = true;
-                        break;
+                        _ => {}
                     }
                 }
-            } else {
-                println!("Invalid action!");
+                let current_player = &mut self.players[seat];
+                let balance = current_player.balance;
+                self.pot += current_player.put_in(balance);
+                let amount = current_player.bet;
+                if amount > self.bet {
+                    self.raise_to(amount);
+                }
+            }
+            Action::Fold => {
+                current_player.folded = true;
             }
         }
-        // TODO: Check if player raised all-in
-        self.advance();
+        self.players[seat].acted = true;
+        self.emit(GameEvent::ActionTaken {
+            seat,
+            action,
+            amount: self.pot - pot_before,
+        });
+        Ok(())
     }
 
-    fn end_game(&mut self) {
-        let mut remaining_players: Vec<Player> = self
+    // Amounts the player can raise the bet to, from min to max.
+    // When they can't afford the smallest raise the only option is all-in.
+    // None when they can't raise at all.
+    pub fn raise_range(&self, seat: usize) -> Option<(u32, u32)> {
+        let player = &self.players[seat];
+        let stack = player.bet + player.balance;
+        // Acting again without a full raise since: someone went all-in for less
+        if stack <= self.bet || player.acted {
+            return None;
+        }
+        let min = self.bet + self.last_raise;
+        let (min, max) = match self.config.betting {
+            Betting::NoLimit => (min, stack),
+            // the pot after calling, on top of the call
+            Betting::PotLimit => {
+                let call = self.bet - player.bet;
+                (min, self.bet + self.pot + call)
+            }
+            Betting::Limit => {
+                if self.raises >= MAX_BETS {
+                    return None;
+                }
+                // a bring-in is completed to one full bet
+                let size = self.street_bet();
+                let to = if self.bet < size {
+                    size
+                } else {
+                    self.bet + size
+                };
+                (to, to)
+            }
+        };
+        Some((min.min(stack), max.min(stack)))
+    }
+
+    // A full bet on the current street: the big blind, or in fixed limit the small
+    // or big bet
+    fn street_bet(&self) -> u32 {
+        match self.config.betting {
+            Betting::Limit => {
+                let round = self.turn.0;
+                self.config.betting.limit_bet(round, self.config.min_bet)
+            }
+            _ => self.config.min_bet * 2,
+        }
+    }
+
+    // The other players have to respond to a new bet.
+    // An all-in raise smaller than the last raise doesn't reopen the betting,
+    // players who already acted can only call it or fold.
+    fn raise_to(&mut self, amount: u32) {
+        let raise = amount - self.bet;
+        let opening = self.bet == 0;
+        self.bet = amount;
+        if raise < self.last_raise && !opening {
+            return;
+        }
+        // at least a full bet, after completing a bring-in
+        self.last_raise = raise.max(self.street_bet());
+        self.raises += 1;
+        for player in self.players.iter_mut() {
+            player.acted = false;
+        }
+    }
+
+    // Splits the chips into a main pot and side pots by each player's total bet.
+    // Every all-in amount of a player still in the hand caps a pot.
+    pub fn pots(&self) -> Vec<Pot> {
+        let mut levels: Vec<u32> = self
             .players
             .iter()
-            .filter(|p| p.is_playing)
-            .cloned()
+            .filter(|p| p.in_hand() && p.all_in)
+            .map(|p| p.total_bet)
             .collect();
+        levels.sort();
+        levels.dedup();
+        levels.push(u32::MAX);
 
-        println!("Game ended!");
-        for player in remaining_players.iter_mut() {
-            println!("{}'s hand: {:?}", player.name, player.get_hand(&self));
+        let mut pots: Vec<Pot> = Vec::new();
+        let mut previous = 0;
+        for level in levels {
+            let amount: u32 = self
+                .players
+                .iter()
+                .map(|p| p.total_bet.min(level) - p.total_bet.min(previous))
+                .sum();
+            let eligible: Vec<usize> = (0..self.players.len())
+                .filter(|&i| self.players[i].in_hand() && self.players[i].total_bet > previous)
+                .collect();
+            previous = level;
+            if amount == 0 {
+                continue;
+            }
+            match pots.last_mut() {
+                // Nobody left to win these chips, or same players as the last pot
+                Some(last) if eligible.is_empty() || last.eligible == eligible => {
+                    last.amount += amount
+                }
+                _ => pots.push(Pot { amount, eligible }),
+            }
         }
+        pots
+    }
 
-        remaining_players.sort_by(|a, b| b.get_hand(self).cmp(&a.get_hand(self)));
-        let winner_hand = remaining_players[0].get_hand(self);
-        // Remove players with worse hands than the winner hand
-        remaining_players.retain(|p| p.get_hand(self) == winner_hand);
-        let num_winners = remaining_players.len();
-
-        println!("Winners:");
-        for winner in remaining_players.iter_mut() {
-            println!("{} won {}$", winner.name, self.pot / num_winners as u32);
-            let new_balance = self.pot / num_winners as u32;
-            winner.balance += new_balance;
+    // Chips of the top bet that nobody matched go back to the bettor,
+    // so they're never raked or counted as won
+    fn return_uncalled(&mut self) {
+        let mut bets: Vec<(u32, usize)> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.total_bet, i))
+            .collect();
+        bets.sort_by(|a, b| b.cmp(a));
+        let (top, seat) = bets[0];
+        let uncalled = top - bets.get(1).map_or(0, |b| b.0);
+        if uncalled == 0 {
+            return;
         }
-        self.ended = true;
+        let player = &mut self.players[seat];
+        player.balance += uncalled;
+        player.bet = player.bet.saturating_sub(uncalled);
+        player.total_bet -= uncalled;
+        self.pot -= uncalled;
+        self.emit(GameEvent::UncalledReturned {
+            seat,
+            amount: uncalled,
+        });
     }
 
-    pub fn print_table(&self) {
-        let card_number = match self.turn {
-            (Round::PreFlop, p) => {
-                println!("PreFlop: {}'s turn", self.players[p].name);
-                0
-            }
-            (Round::Flop, p) => {
-                println!("Flop: {}'s turn", self.players[p].name);
-                3
+    fn end_game(&mut self) {
+        self.return_uncalled();
+        let pots = self.pots();
+        let in_hand: Vec<usize> = (0..self.players.len())
+            .filter(|&i| self.players[i].in_hand())
+            .collect();
+
+        let variant = self.config.variant;
+        // Hands and qualifying lows by seat on every run of the board, only when there's
+        // a showdown and for players still in the hand. Lows only in hi-lo games.
+        let runs: Vec<RunHands> = self
+            .boards()
+            .iter()
+            .map(|board| {
+                let hands = self
+                    .players
+                    .iter()
+                    .map(|p| {
+                        let contested = p.in_hand() && in_hand.len() > 1;
+                        contested.then(|| p.get_hand_on(self, board))
+                    })
+                    .collect();
+                let lows = self
+                    .players
+                    .iter()
+                    .map(|p| {
+                        let contested = p.in_hand() && in_hand.len() > 1;
+                        contested
+                            .then(|| variant.low_hand(&p.cards(), board))
+                            .flatten()
+                    })
+                    .collect();
+                (hands, lows)
+            })
+            .collect();
+        if in_hand.len() > 1 {
+            for (run, (hands, lows)) in runs.iter().enumerate() {
+                let shown = in_hand
+                    .iter()
+                    .map(|&i| (i, hands[i].clone().unwrap()))
+                    .collect();
+                let shown_lows = in_hand
+                    .iter()
+                    .filter_map(|&i| lows[i].clone().map(|low| (i, low)))
+                    .collect();
+                self.emit(GameEvent::Showdown {
+                    run: run + 1,
+                    hands: shown,
+                    lows: shown_lows,
+                });
             }
-            (Round::Turn, p) => {
-                println!("Turn: {}'s turn", self.players[p].name);
-                4
+        }
+
+        // No flop, no drop: hands that end in the first betting round aren't raked
+        let rake = self
+            .config
+            .rake
+            .filter(|r| !r.no_flop_no_drop || self.turn.0 != variant.first_round());
+        for (i, pot) in pots.iter().enumerate() {
+            let mut amount = pot.amount;
+            if let Some(rake) = rake {
+                let taken = rake.take(amount, self.rake);
+                if taken > 0 {
+                    amount -= taken;
+                    self.rake += taken;
+                    self.emit(GameEvent::RakeTaken {
+                        pot: i,
+                        amount: taken,
+                    });
+                }
             }
-            (Round::River, p) => {
-                println!("River: {}'s turn", self.players[p].name);
-                5
+            // Each run of the board wins an equal share, the first runs get the odd chips
+            let count = runs.len() as u32;
+            for (run, (hands, lows)) in runs.iter().enumerate() {
+                let share = amount / count + ((run as u32) < amount % count) as u32;
+                self.award_share(i, share, &pot.eligible, hands, lows);
             }
-            (Round::Showdown, _) => {
-                println!("Showdown");
-                5
+        }
+        self.pot = 0;
+        self.ended = true;
+        self.emit(GameEvent::HandEnded);
+
+        if let Some(dir) = &self.history_dir {
+            let path = dir.join(format!("hand_{}.txt", self.hand_seed));
+            if let Err(error) = self.history().save(&path) {
+                self.emit(GameEvent::HistoryNotSaved {
+                    path: path.display().to_string(),
+                    error: error.to_string(),
+                });
             }
-        };
+        }
+        self.check_ledger();
+    }
+
+    // Stacks, the pot and the rake taken, in chips
+    pub fn chip_total(&self) -> u32 {
+        self.players.iter().map(|p| p.balance).sum::<u32>() + self.pot + self.rake
+    }
+
+    // Chips are never created or lost during a hand, anything else is a bug
+    fn check_ledger(&self) {
+        let total = self.chip_total();
+        if total != self.chips {
+            panic!(
+                "Chips not conserved in hand {}: {}$ at the start, {}$ at the end!",
+                self.hand_seed, self.chips, total
+            );
+        }
+    }
+
+    // Awards a pot, or one run's share of it, to the best hand among the eligible players
+    fn award_share(
+        &mut self,
+        pot: usize,
+        amount: u32,
+        eligible: &[usize],
+        hands: &[Option<BestHand>],
+        lows: &[Option<LowHand>],
+    ) {
+        // Nobody to beat, and maybe no board to make a hand with
+        if let [winner] = eligible {
+            self.award(pot, amount, &[*winner]);
+            return;
+        }
+        let variant = self.config.variant;
+        let hand = |p: usize| &hands[p].as_ref().unwrap().hand;
+        let winner_hand = eligible
+            .iter()
+            .map(|&p| hand(p))
+            .max_by(|a, b| variant.compare_hands(a, b))
+            .unwrap();
+        // Remove players with worse hands than the winner hand
+        let winners: Vec<usize> = eligible
+            .iter()
+            .copied()
+            .filter(|&p| variant.compare_hands(hand(p), winner_hand) == Equal)
+            .collect();
+        let low_winners = best_lows(lows, eligible);
+        if low_winners.is_empty() || low_winners == winners {
+            self.award(pot, amount, &winners);
+        } else {
+            // Split between high and low, the high half gets the odd chip
+            let low_half = amount / 2;
+            self.award(pot, amount - low_half, &winners);
+            self.award(pot, low_half, &low_winners);
+        }
+    }
+
+    // Splits a pot between its winners. Odd chips that can't be split go one
+    // each to the winners in odd_chip_order.
+    fn award(&mut self, pot: usize, amount: u32, winners: &[usize]) {
+        let share = amount / winners.len() as u32;
+        let odd_chips = amount as usize % winners.len();
+        let order = self.odd_chip_order(winners);
+        for &winner in winners.iter() {
+            let odd_chip = order[..odd_chips].contains(&winner) as u32;
+            self.players[winner].balance += share + odd_chip;
+            self.emit(GameEvent::PotAwarded {
+                pot,
+                seat: winner,
+                amount: share + odd_chip,
+            });
+        }
+    }
+
+    // The first winner left of the button gets the first odd chip.
+    // In stud the winner with the highest card does, by rank then suit.
+    fn odd_chip_order(&self, winners: &[usize]) -> Vec<usize> {
+        let mut order = winners.to_vec();
+        if self.config.variant.is_stud() {
+            let high_card = |seat: usize| {
+                let cards = self.players[seat].cards();
+                cards.iter().map(crate::stud::bring_in_order).max()
+            };
+            order.sort_by_key(|&seat| std::cmp::Reverse(high_card(seat)));
+        } else {
+            let len = self.players.len();
+            order.sort_by_key(|&seat| (seat + len - self.button - 1) % len);
+        }
+        order
+    }
+
+    pub fn history(&self) -> crate::history::HandHistory {
+        crate::history::HandHistory::from_game(self)
+    }
+
+    pub fn print_table(&self) {
+        match self.turn {
+            (Round::Showdown, _) => println!("Showdown"),
+            (round, p) => println!("{:?}: {}'s turn", round, self.players[p].name),
+        }
         println!("Table:");
-        for i in 0..card_number {
-            println!("{:?} of {:?}", self.table[i].rank, self.table[i].suit);
+        for card in self.board() {
+            println!("{:#}", card);
         }
+        for player in self
+            .players
+            .iter()
+            .filter(|p| p.in_hand() && !p.upcards.is_empty())
+        {
+            println!("{} shows: {:+}", player.name, Cards(player.upcards.clone()));
+        }
+    }
+}
+
+// Seats with the best qualifying low among the eligible ones, if any qualifies
+fn best_lows(lows: &[Option<LowHand>], eligible: &[usize]) -> Vec<usize> {
+    let best = eligible
+        .iter()
+        .filter_map(|&p| lows[p].as_ref())
+        .max_by(|a, b| a.compare(b));
+    match best {
+        Some(best) => eligible
+            .iter()
+            .copied()
+            .filter(|&p| {
+                lows[p]
+                    .as_ref()
+                    .is_some_and(|low| low.compare(best) == Equal)
+            })
+            .collect(),
+        None => Vec::new(),
     }
 }
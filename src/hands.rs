@@ -1,319 +1,728 @@
 // hands.rs
 use crate::poker::*;
 use std::cmp::Ordering::{self, *};
-use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq)]
 pub enum Hand {
     HighCard(Rank, Rank, Rank, Rank, Rank),
     Pair(Rank, Rank, Rank, Rank),
     TwoPair(Rank, Rank, Rank),
     ThreeOfAKind(Rank, Rank, Rank),
     Straight(Rank),
-    Flush(Rank),
+    Flush(Rank, Rank, Rank, Rank, Rank),
     FullHouse(Rank, Rank),
     FourOfAKind(Rank, Rank),
     StraightFlush(Rank),
     RoyalFlush,
+    // Only with wild cards
+    FiveOfAKind(Rank),
 }
 
 impl Hand {
+    // 0 for HighCard up to 9 for RoyalFlush, 10 for FiveOfAKind
+    pub fn category(&self) -> u8 {
+        match self {
+            Hand::HighCard(..) => 0,
+            Hand::Pair(..) => 1,
+            Hand::TwoPair(..) => 2,
+            Hand::ThreeOfAKind(..) => 3,
+            Hand::Straight(..) => 4,
+            Hand::Flush(..) => 5,
+            Hand::FullHouse(..) => 6,
+            Hand::FourOfAKind(..) => 7,
+            Hand::StraightFlush(..) => 8,
+            Hand::RoyalFlush => 9,
+            Hand::FiveOfAKind(..) => 10,
+        }
+    }
+
+    // Higher category wins, then the ranks in tie_break_ranks order
     pub fn compare(&self, other: &Hand) -> Ordering {
-        use Hand::*;
-        match (self, other) {
-            (RoyalFlush, RoyalFlush) => Equal,
-            (StraightFlush(max), StraightFlush(o_max)) => max.cmp(o_max),
-            (FourOfAKind(rank, k), FourOfAKind(o_rank, o_k)) => match rank.cmp(o_rank) {
-                Greater => Greater,
-                Less => Less,
-                Equal => k.cmp(o_k),
-            },
-            (FullHouse(trio, pair), FullHouse(o_trio, o_pair)) => match trio.cmp(o_trio) {
-                Greater => Greater,
-                Less => Less,
-                Equal => pair.cmp(o_pair),
-            },
-            (Flush(max), Flush(o_max)) => max.cmp(o_max),
-            (Straight(max), Straight(o_max)) => max.cmp(o_max),
-            (ThreeOfAKind(trio, k1, k2), ThreeOfAKind(o_trio, o_k1, o_k2)) => {
-                match trio.cmp(o_trio) {
-                    Greater => Greater,
-                    Less => Less,
-                    Equal => match k1.cmp(o_k1) {
-                        Greater => Greater,
-                        Less => Less,
-                        Equal => k2.cmp(o_k2),
-                    },
-                }
+        self.category()
+            .cmp(&other.category())
+            .then_with(|| self.tie_break_ranks().cmp(&other.tie_break_ranks()))
+    }
+
+    // Explains why this hand beats the loser, e.g. "wins with a flush over a straight"
+    // or "wins on the second kicker, Nine over Eight".
+    // Ranks are checked in the same order as in compare.
+    pub fn explain_win(&self, loser: &Hand) -> String {
+        if self.category() != loser.category() {
+            return format!("wins with {} over {}", self.name(), loser.name());
+        }
+        let decider = self
+            .tie_break_labels()
+            .iter()
+            .zip(self.tie_break_ranks().into_iter().flatten())
+            .zip(loser.tie_break_ranks().into_iter().flatten())
+            .find(|((_, rank), o_rank)| rank != o_rank);
+        match decider {
+            Some(((&(label, set), rank), o_rank)) => {
+                let name = |r: Rank| if set { plural(r) } else { format!("{:#}", r) };
+                format!(
+                    "wins on the {}, {} over {}",
+                    label,
+                    name(rank),
+                    name(o_rank)
+                )
             }
-            (TwoPair(pair1, pair2, k), TwoPair(o_pair1, o_pair2, o_k)) => {
-                match pair1.cmp(o_pair1) {
-                    Greater => Greater,
-                    Less => Less,
-                    Equal => match pair2.cmp(o_pair2) {
-                        Greater => Greater,
-                        Less => Less,
-                        Equal => k.cmp(o_k),
-                    },
-                }
+            None => "ties with the same hand".to_string(),
+        }
+    }
+
+    // The category with an article, e.g. "a flush" or "two pair"
+    fn name(&self) -> &'static str {
+        match self {
+            Hand::HighCard(..) => "high card",
+            Hand::Pair(..) => "a pair",
+            Hand::TwoPair(..) => "two pair",
+            Hand::ThreeOfAKind(..) => "three of a kind",
+            Hand::Straight(..) => "a straight",
+            Hand::Flush(..) => "a flush",
+            Hand::FullHouse(..) => "a full house",
+            Hand::FourOfAKind(..) => "four of a kind",
+            Hand::StraightFlush(..) => "a straight flush",
+            Hand::RoyalFlush => "a royal flush",
+            Hand::FiveOfAKind(..) => "five of a kind",
+        }
+    }
+
+    // Ranks deciding between hands of the same category, most important first.
+    // Fixed size so comparing hands doesn't allocate.
+    fn tie_break_ranks(&self) -> [Option<Rank>; HAND_SIZE] {
+        let ranked = |ranks: &[Rank]| {
+            let mut out = [None; HAND_SIZE];
+            for (slot, &rank) in out.iter_mut().zip(ranks) {
+                *slot = Some(rank);
             }
-            (Pair(pair, k1, k2, k3), Pair(o_pair, o_k1, o_k2, o_k3)) => match pair.cmp(o_pair) {
-                Greater => Greater,
-                Less => Less,
-                Equal => match k1.cmp(o_k1) {
-                    Greater => Greater,
-                    Less => Less,
-                    Equal => match k2.cmp(o_k2) {
-                        Greater => Greater,
-                        Less => Less,
-                        Equal => k3.cmp(o_k3),
-                    },
-                },
-            },
-            (HighCard(k1, k2, k3, k4, k5), HighCard(o_k1, o_k2, o_k3, o_k4, o_k5)) => {
-                match k1.cmp(o_k1) {
-                    Greater => Greater,
-                    Less => Less,
-                    Equal => match k2.cmp(o_k2) {
-                        Greater => Greater,
-                        Less => Less,
-                        Equal => match k3.cmp(o_k3) {
-                            Greater => Greater,
-                            Less => Less,
-                            Equal => match k4.cmp(o_k4) {
-                                Greater => Greater,
-                                Less => Less,
-                                Equal => k5.cmp(o_k5),
-                            },
-                        },
-                    },
-                }
+            out
+        };
+        match *self {
+            Hand::HighCard(k1, k2, k3, k4, k5) | Hand::Flush(k1, k2, k3, k4, k5) => {
+                ranked(&[k1, k2, k3, k4, k5])
             }
-            _ => self.cmp(other),
+            Hand::Pair(pair, k1, k2, k3) => ranked(&[pair, k1, k2, k3]),
+            Hand::TwoPair(pair1, pair2, k) => ranked(&[pair1, pair2, k]),
+            Hand::ThreeOfAKind(trio, k1, k2) => ranked(&[trio, k1, k2]),
+            Hand::Straight(max) | Hand::StraightFlush(max) => ranked(&[max]),
+            Hand::FullHouse(trio, pair) => ranked(&[trio, pair]),
+            Hand::FourOfAKind(quad, k) => ranked(&[quad, k]),
+            Hand::RoyalFlush => ranked(&[]),
+            Hand::FiveOfAKind(rank) => ranked(&[rank]),
+        }
+    }
+
+    // What each of the tie_break_ranks is called.
+    // set: the rank is a pair, trio or quad
+    fn tie_break_labels(&self) -> &'static [(&'static str, bool)] {
+        match self {
+            Hand::HighCard(..) | Hand::Flush(..) => &[
+                ("high card", false),
+                ("second card", false),
+                ("third card", false),
+                ("fourth card", false),
+                ("fifth card", false),
+            ],
+            Hand::Pair(..) => &[
+                ("pair", true),
+                ("kicker", false),
+                ("second kicker", false),
+                ("third kicker", false),
+            ],
+            Hand::TwoPair(..) => &[("top pair", true), ("second pair", true), ("kicker", false)],
+            Hand::ThreeOfAKind(..) => &[
+                ("three of a kind", true),
+                ("kicker", false),
+                ("second kicker", false),
+            ],
+            Hand::Straight(..) | Hand::StraightFlush(..) => &[("high card", false)],
+            Hand::FullHouse(..) => &[("three of a kind", true), ("pair", true)],
+            Hand::FourOfAKind(..) => &[("four of a kind", true), ("kicker", false)],
+            Hand::RoyalFlush => &[],
+            Hand::FiveOfAKind(..) => &[("five of a kind", true)],
         }
     }
 }
 
+// Reads like a dealer, e.g. "Full house, Kings full of Tens"
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Hand::HighCard(k1, k2, k3, k4, k5) => {
+                write!(f, "High card, {:#} with {}", k1, list(&[k2, k3, k4, k5]))
+            }
+            Hand::Pair(pair, k1, k2, k3) => write!(
+                f,
+                "Pair of {} with {} kickers",
+                plural(pair),
+                list(&[k1, k2, k3])
+            ),
+            Hand::TwoPair(pair1, pair2, k) => write!(
+                f,
+                "Two pair, {} and {} with {} {:#} kicker",
+                plural(pair1),
+                plural(pair2),
+                article(k),
+                k
+            ),
+            Hand::ThreeOfAKind(trio, k1, k2) => write!(
+                f,
+                "Three of a kind, {} with {} kickers",
+                plural(trio),
+                list(&[k1, k2])
+            ),
+            Hand::Straight(max) => write!(f, "Straight, {:#} high", max),
+            Hand::Flush(k1, k2, k3, k4, k5) => {
+                write!(f, "Flush, {:#} high with {}", k1, list(&[k2, k3, k4, k5]))
+            }
+            Hand::FullHouse(trio, pair) => {
+                write!(f, "Full house, {} full of {}", plural(trio), plural(pair))
+            }
+            Hand::FourOfAKind(quad, k) => write!(
+                f,
+                "Four of a kind, {} with {} {:#} kicker",
+                plural(quad),
+                article(k),
+                k
+            ),
+            Hand::StraightFlush(max) => write!(f, "Straight flush, {:#} high", max),
+            Hand::RoyalFlush => write!(f, "Royal flush"),
+            Hand::FiveOfAKind(rank) => write!(f, "Five of a kind, {}", plural(rank)),
+        }
+    }
+}
+
+fn plural(rank: Rank) -> String {
+    match rank {
+        Rank::Six => "Sixes".to_string(),
+        _ => format!("{:#}s", rank),
+    }
+}
+
+fn article(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Ace | Rank::Eight => "an",
+        _ => "a",
+    }
+}
+
+// "Ace, Nine and Four"
+fn list(ranks: &[Rank]) -> String {
+    let names: Vec<String> = ranks.iter().map(|r| format!("{:#}", r)).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => names.concat(),
+    }
+}
+
+// A hand together with the five cards that make it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestHand {
+    pub hand: Hand,
+    pub cards: [Card; HAND_SIZE],
+}
+
+// Ranks that make the lowest straight together with an Ace
+pub const WHEEL: [Rank; 4] = [Rank::Two, Rank::Three, Rank::Four, Rank::Five];
+pub const SHORT_DECK_WHEEL: [Rank; 4] = [Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine];
+
 pub fn calculate_hand(cards: &[Card]) -> Hand {
-    if let Some(hand) = is_straight_flush(cards) {
-        if hand == Hand::StraightFlush(Rank::Ace) {
+    calculate_hand_with_wheel(cards, &WHEEL)
+}
+
+// Short deck has no Two to Five, A-6-7-8-9 is the lowest straight instead
+pub fn calculate_short_deck_hand(cards: &[Card]) -> Hand {
+    calculate_hand_with_wheel(cards, &SHORT_DECK_WHEEL)
+}
+
+// Same as calculate_hand, also picking the five cards used.
+// Slower, use it only when the cards are needed.
+pub fn calculate_best_hand(cards: &[Card]) -> BestHand {
+    let hand = calculate_hand(cards);
+    BestHand {
+        cards: best_cards(cards, &hand, &WHEEL),
+        hand,
+    }
+}
+
+pub fn calculate_short_deck_best_hand(cards: &[Card]) -> BestHand {
+    let hand = calculate_short_deck_hand(cards);
+    BestHand {
+        cards: best_cards(cards, &hand, &SHORT_DECK_WHEEL),
+        hand,
+    }
+}
+
+fn calculate_hand_with_wheel(cards: &[Card], wheel: &[Rank; 4]) -> Hand {
+    // Ranks as bitmasks, per suit and by how many times they appear.
+    // Everything stays on the stack, this runs millions of times in simulations.
+    let mut counts = [0u8; 13];
+    let mut suits: [RankMask; 4] = [0; 4];
+    let mut all: RankMask = 0;
+    for card in cards.iter() {
+        counts[card.rank as usize] += 1;
+        suits[card.suit as usize] |= rank_bit(card.rank);
+        all |= rank_bit(card.rank);
+    }
+    let (mut quads, mut trips, mut pairs): (RankMask, RankMask, RankMask) = (0, 0, 0);
+    for (i, count) in counts.iter().enumerate() {
+        match count {
+            4.. => quads |= 1 << i,
+            3 => trips |= 1 << i,
+            2 => pairs |= 1 << i,
+            _ => {}
+        }
+    }
+    // a higher mask means a higher flush
+    let flush = suits.iter().copied().filter(|s| s.count_ones() >= 5).max();
+
+    if let Some(high) = suits.iter().filter_map(|s| straight_high(*s, wheel)).max() {
+        if high == Rank::Ace {
             return Hand::RoyalFlush;
-        } else {
-            return hand;
         }
-    } else if let Some(hand) = is_four_of_a_kind(cards) {
-        return hand;
-    } else if let Some(hand) = is_full_house(cards) {
-        return hand;
-    } else if let Some(hand) = is_flush(cards) {
-        return hand;
-    } else if let Some(hand) = is_straight(cards) {
-        return hand;
-    } else if let Some(hand) = is_three_of_a_kind(cards) {
-        return hand;
-    } else if let Some(hand) = is_pairs(cards) {
-        return hand;
-    }
-    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
-    ranks.sort();
-    let l = ranks.len();
+        return Hand::StraightFlush(high);
+    }
+    if quads != 0 {
+        let quad = highest(quads);
+        return Hand::FourOfAKind(quad, highest(all & !rank_bit(quad)));
+    }
+    if trips != 0 {
+        // With two trios the lower one is used as the pair
+        let trio = highest(trips);
+        let rest = (trips | pairs) & !rank_bit(trio);
+        if rest != 0 {
+            return Hand::FullHouse(trio, highest(rest));
+        }
+    }
+    if let Some(mut flush) = flush {
+        return Hand::Flush(
+            pop_highest(&mut flush),
+            pop_highest(&mut flush),
+            pop_highest(&mut flush),
+            pop_highest(&mut flush),
+            pop_highest(&mut flush),
+        );
+    }
+    if let Some(high) = straight_high(all, wheel) {
+        return Hand::Straight(high);
+    }
+    if trips != 0 {
+        let trio = highest(trips);
+        let mut kickers = all & !rank_bit(trio);
+        return Hand::ThreeOfAKind(trio, pop_highest(&mut kickers), pop_highest(&mut kickers));
+    }
+    if pairs.count_ones() >= 2 {
+        // With three pairs the lowest one can be the kicker
+        let high_pair = pop_highest(&mut pairs);
+        let low_pair = pop_highest(&mut pairs);
+        let kicker = highest(all & !rank_bit(high_pair) & !rank_bit(low_pair));
+        return Hand::TwoPair(high_pair, low_pair, kicker);
+    }
+    if pairs != 0 {
+        let pair = highest(pairs);
+        let mut kickers = all & !rank_bit(pair);
+        return Hand::Pair(
+            pair,
+            pop_highest(&mut kickers),
+            pop_highest(&mut kickers),
+            pop_highest(&mut kickers),
+        );
+    }
+    let mut kickers = all;
     Hand::HighCard(
-        ranks[l - 1],
-        ranks[l - 2],
-        ranks[l - 3],
-        ranks[l - 4],
-        ranks[l - 5],
+        pop_highest(&mut kickers),
+        pop_highest(&mut kickers),
+        pop_highest(&mut kickers),
+        pop_highest(&mut kickers),
+        pop_highest(&mut kickers),
     )
 }
 
-//
-// Functions to check if a set of cards can be made into each hand
-//
-fn is_straight_flush(cards: &[Card]) -> Option<Hand> {
-    // separate cards by suit:
-    let mut suits: HashMap<Suit, Vec<Card>> = HashMap::new();
-    for card in cards.iter() {
-        suits.entry(card.suit).or_insert(Vec::new()).push(*card);
-    }
-    // check for straights in each suit
-    for (_, card_vec) in suits.iter() {
-        if card_vec.len() < 5 {
-            continue;
-        } else if let Some(Hand::Straight(rank)) = is_straight(card_vec.as_slice()) {
-            return Some(Hand::StraightFlush(rank));
-        }
-    }
-    None
+// Omaha hands use exactly two hole cards and three board cards
+pub fn calculate_omaha_hand(hole: &[Card], board: &[Card]) -> Hand {
+    calculate_omaha_best_hand(hole, board).hand
 }
 
-fn is_four_of_a_kind(cards: &[Card]) -> Option<Hand> {
-    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
-    ranks.sort();
-    // separate cards by rank:
-    let mut counts: HashMap<Rank, usize> = HashMap::new();
-    for rank in ranks.iter() {
-        *counts.entry(*rank).or_insert(0) += 1;
-    }
-    for (rank, count) in counts.iter() {
-        if *count == 4 {
-            let mut other = ranks.clone();
-            other.retain(|r| r != rank);
-            other.sort();
-            return Some(Hand::FourOfAKind(*rank, other[other.len() - 1]));
+pub fn calculate_omaha_best_hand(hole: &[Card], board: &[Card]) -> BestHand {
+    let mut best: Option<BestHand> = None;
+    for (i, j) in pairs(hole.len()) {
+        for (k, l, m) in triples(board.len()) {
+            let cards = [hole[i], hole[j], board[k], board[l], board[m]];
+            let hand = calculate_hand(&cards);
+            if best
+                .as_ref()
+                .is_none_or(|b| hand.compare(&b.hand) == Greater)
+            {
+                best = Some(BestHand { hand, cards });
+            }
         }
     }
-    None
+    best.expect("Omaha needs at least 2 hole cards and 3 board cards")
 }
 
-fn is_full_house(cards: &[Card]) -> Option<Hand> {
-    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
-    ranks.sort();
-    // separate cards by rank:
-    let mut counts: HashMap<Rank, usize> = HashMap::new();
-    for rank in ranks.iter() {
-        *counts.entry(*rank).or_insert(0) += 1;
-    }
-    let mut trio: Option<Rank> = None;
-    let mut pair: Option<Rank> = None;
-    // find a trio and pair
-    for (rank, count) in counts.iter() {
-        if *count == 3 {
-            trio = Some(*rank);
-        } else if *count == 2 {
-            match pair {
-                None => pair = Some(*rank),
-                // if there's already a pair, use new pair only if higher
-                Some(current_rank) => {
-                    if *rank > current_rank {
-                        pair = Some(*rank);
-                    }
-                }
+//
+// Wild cards: jokers, and every card of the wild ranks in games like deuces wild.
+// A wild card stands for any card, even one that's already in the hand.
+//
+pub fn is_wild(card: &Card, wild: &[Rank]) -> bool {
+    card.rank == Rank::Joker || wild.contains(&card.rank)
+}
+
+pub fn calculate_wild_hand(cards: &[Card], wild: &[Rank]) -> Hand {
+    wild_hands(cards, wild, &WHEEL)
+        .into_iter()
+        .max_by(|a, b| a.compare(b))
+        .unwrap()
+}
+
+// The best hand of each category the cards can make, so variants that rank
+// categories differently can pick their own best one
+pub fn wild_hands(cards: &[Card], wild: &[Rank], wheel: &[Rank; 4]) -> Vec<Hand> {
+    let wilds = cards.iter().filter(|c| is_wild(c, wild)).count() as u8;
+    if wilds == 0 {
+        return vec![calculate_hand_with_wheel(cards, wheel)];
+    }
+    let mut counts = [0u8; 13];
+    let mut suits: [RankMask; 4] = [0; 4];
+    let mut all: RankMask = 0;
+    for card in cards.iter().filter(|c| !is_wild(c, wild)) {
+        counts[card.rank as usize] += 1;
+        suits[card.suit as usize] |= rank_bit(card.rank);
+        all |= rank_bit(card.rank);
+    }
+    // Ranks in the deck from high to low, wild cards can only be these
+    let ranks: Vec<Rank> = RANKS[wheel[0] as usize..].iter().rev().copied().collect();
+    // Straights from high to low, by the five ranks they need
+    let mut straights: Vec<[Rank; HAND_SIZE]> = (wheel[0] as usize + 4..RANKS.len())
+        .rev()
+        .map(|top| [0, 1, 2, 3, 4].map(|i| RANKS[top - i]))
+        .collect();
+    straights.push([wheel[3], wheel[2], wheel[1], wheel[0], Rank::Ace]);
+    let missing = |needed: &[Rank], mask: RankMask| {
+        needed.iter().filter(|&&r| mask & rank_bit(r) == 0).count() as u8
+    };
+    // Wild cards needed to have `size` cards of the rank
+    let short = |rank: Rank, size: u8| size.saturating_sub(counts[rank as usize]);
+    // Highest different ranks out of the mask, filled in with the left over wild cards
+    let kickers = |mask: RankMask, excluded: &[Rank], size: usize, mut left: u8| {
+        let mut picked = Vec::new();
+        for &rank in ranks.iter().filter(|r| !excluded.contains(r)) {
+            if picked.len() == size {
+                break;
+            }
+            if mask & rank_bit(rank) != 0 {
+                picked.push(rank);
+            } else if left > 0 {
+                left -= 1;
+                picked.push(rank);
             }
         }
+        picked
+    };
+    // Highest rank there can be `size` of
+    let group = |size: u8| ranks.iter().copied().find(|&r| short(r, size) <= wilds);
+
+    let mut hands = Vec::new();
+    if let Some(rank) = group(5) {
+        hands.push(Hand::FiveOfAKind(rank));
     }
-    if let (Some(trio), Some(pair)) = (trio, pair) {
-        Some(Hand::FullHouse(trio, pair))
-    } else {
-        None
+    for suit in suits.iter() {
+        if let Some(straight) = straights.iter().find(|s| missing(*s, *suit) <= wilds) {
+            hands.push(match straight[0] {
+                Rank::Ace => Hand::RoyalFlush,
+                high => Hand::StraightFlush(high),
+            });
+        }
+    }
+    if let Some(quad) = group(4) {
+        if let [k] = kickers(all, &[quad], 1, wilds - short(quad, 4))[..] {
+            hands.push(Hand::FourOfAKind(quad, k));
+        }
+    }
+    let full_house = ranks.iter().find_map(|&trio| {
+        ranks
+            .iter()
+            .find(|&&pair| pair != trio && short(trio, 3) + short(pair, 2) <= wilds)
+            .map(|&pair| Hand::FullHouse(trio, pair))
+    });
+    hands.extend(full_house);
+    for suit in suits.iter() {
+        if suit.count_ones() as u8 + wilds >= 5 {
+            let k = kickers(*suit, &[], 5, wilds);
+            hands.push(Hand::Flush(k[0], k[1], k[2], k[3], k[4]));
+        }
+    }
+    if let Some(straight) = straights.iter().find(|s| missing(*s, all) <= wilds) {
+        hands.push(Hand::Straight(straight[0]));
+    }
+    if let Some(trio) = group(3) {
+        if let [k1, k2] = kickers(all, &[trio], 2, wilds - short(trio, 3))[..] {
+            hands.push(Hand::ThreeOfAKind(trio, k1, k2));
+        }
+    }
+    // With a wild card there's always a pair, and trips whenever there'd be two pair.
+    // The kickers must be different ranks, a better hand is made otherwise.
+    if let Some(pair) = group(2) {
+        if let [k1, k2, k3] = kickers(all, &[pair], 3, wilds - short(pair, 2))[..] {
+            hands.push(Hand::Pair(pair, k1, k2, k3));
+        }
     }
+    hands
 }
 
-fn is_flush(cards: &[Card]) -> Option<Hand> {
-    // separate cards by suit:
-    let mut suits: HashMap<Suit, Vec<Card>> = HashMap::new();
-    for card in cards.iter() {
-        suits.entry(card.suit).or_insert(Vec::new()).push(*card);
+// Every five card hand out of the cards, with exactly two hole cards in Omaha
+pub fn five_card_hands(hole: &[Card], board: &[Card], omaha: bool) -> Vec<[Card; HAND_SIZE]> {
+    if omaha {
+        return pairs(hole.len())
+            .flat_map(|(i, j)| {
+                triples(board.len())
+                    .map(move |(k, l, m)| [hole[i], hole[j], board[k], board[l], board[m]])
+            })
+            .collect();
     }
-    // check for flushes in each suit
-    for (_, card_vec) in suits.iter() {
-        if card_vec.len() < 5 {
-            continue;
+    let cards: Vec<Card> = hole.iter().chain(board.iter()).copied().collect();
+    fives(cards.len())
+        .map(|five| five.map(|i| cards[i]))
+        .collect()
+}
+
+// Picks the cards that make the hand, highest first
+fn best_cards(cards: &[Card], hand: &Hand, wheel: &[Rank; 4]) -> [Card; HAND_SIZE] {
+    use Hand::*;
+    // The five ranks of the straight with the given top card
+    let straight = |high: Rank, suit: Option<Suit>| -> [Rank; HAND_SIZE] {
+        let top = high as usize;
+        if top >= 4 && (top - 4..=top).all(|r| has(cards, RANKS[r], suit)) {
+            [
+                RANKS[top],
+                RANKS[top - 1],
+                RANKS[top - 2],
+                RANKS[top - 3],
+                RANKS[top - 4],
+            ]
+        } else {
+            [wheel[3], wheel[2], wheel[1], wheel[0], Rank::Ace]
         }
-        let mut ranks = card_vec.iter().map(|c| c.rank).collect::<Vec<_>>();
-        ranks.sort();
-        return Some(Hand::Flush(ranks[ranks.len() - 1]));
-    }
-    None
+    };
+    // Suit that has all the given ranks
+    let suited = |ranks: &[Rank]| {
+        SUITS
+            .iter()
+            .copied()
+            .find(|&suit| ranks.iter().all(|&r| has(cards, r, Some(suit))))
+    };
+    let (ranks, suit) = match *hand {
+        RoyalFlush | StraightFlush(_) => {
+            let high = match *hand {
+                StraightFlush(high) => high,
+                _ => Rank::Ace,
+            };
+            let suit = SUITS
+                .iter()
+                .copied()
+                .find(|&suit| suited(&straight(high, Some(suit))) == Some(suit));
+            (straight(high, suit), suit)
+        }
+        FiveOfAKind(rank) => ([rank; HAND_SIZE], None),
+        FourOfAKind(quad, k) => ([quad, quad, quad, quad, k], None),
+        FullHouse(trio, pair) => ([trio, trio, trio, pair, pair], None),
+        Flush(k1, k2, k3, k4, k5) => {
+            let ranks = [k1, k2, k3, k4, k5];
+            (ranks, suited(&ranks))
+        }
+        Straight(high) => (straight(high, None), None),
+        ThreeOfAKind(trio, k1, k2) => ([trio, trio, trio, k1, k2], None),
+        TwoPair(pair1, pair2, k) => ([pair1, pair1, pair2, pair2, k], None),
+        Pair(pair, k1, k2, k3) => ([pair, pair, k1, k2, k3], None),
+        HighCard(k1, k2, k3, k4, k5) => ([k1, k2, k3, k4, k5], None),
+    };
+
+    let mut used = vec![false; cards.len()];
+    ranks.map(|rank| {
+        let i = (0..cards.len())
+            .find(|&i| !used[i] && cards[i].rank == rank && suit.is_none_or(|s| cards[i].suit == s))
+            .expect("The hand wasn't made from these cards");
+        used[i] = true;
+        cards[i]
+    })
 }
 
-fn is_straight(cards: &[Card]) -> Option<Hand> {
-    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
-    ranks.sort();
-    // remove duplicates
-    ranks.dedup();
-    if ranks.len() < 5 {
-        return None;
-    }
-    // special case for ace low straight
-    if ranks[0] == Rank::Two
-        && ranks[1] == Rank::Three
-        && ranks[2] == Rank::Four
-        && ranks[3] == Rank::Five
-        && ranks[ranks.len() - 1] == Rank::Ace
-    {
-        return Some(Hand::Straight(Rank::Five));
-    }
-    // check for straights in each subset of length 5
-    for i in (0..=(ranks.len() - 5)).rev() {
-        let mut straight = true;
-        for j in i..(i + 5 - 1) {
-            if (ranks[j] as u8 + 1) != (ranks[j + 1] as u8) {
-                straight = false;
-                break;
+fn has(cards: &[Card], rank: Rank, suit: Option<Suit>) -> bool {
+    cards
+        .iter()
+        .any(|c| c.rank == rank && suit.is_none_or(|s| c.suit == s))
+}
+
+fn pairs(n: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..n).flat_map(move |i| ((i + 1)..n).map(move |j| (i, j)))
+}
+
+fn triples(n: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+    pairs(n).flat_map(move |(i, j)| ((j + 1)..n).map(move |k| (i, j, k)))
+}
+
+fn fives(n: usize) -> impl Iterator<Item = [usize; 5]> {
+    triples(n).flat_map(move |(i, j, k)| {
+        pairs(n)
+            .filter(move |&(l, _)| l > k)
+            .map(move |(l, m)| [i, j, k, l, m])
+    })
+}
+
+//
+// Low hands, for lowball and hi-lo games
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LowHand {
+    // Aces are low and straights and flushes don't count.
+    // The number is how bad the pairs are: 0 for none up to 5 for four of a kind.
+    // Ranks go by group, pairs first, then from high to low.
+    AceToFive(u8, [Rank; HAND_SIZE]),
+    // Aces are high and straights and flushes count, the worst high hand wins.
+    // A-2-3-4-5 is Ace high, not a straight.
+    DeuceToSeven(Hand),
+}
+
+impl LowHand {
+    // Greater for the better (lower) hand, same as Hand::compare
+    pub fn compare(&self, other: &LowHand) -> Ordering {
+        match (self, other) {
+            (LowHand::AceToFive(pairs, ranks), LowHand::AceToFive(o_pairs, o_ranks)) => {
+                (o_pairs, o_ranks.map(ace_low)).cmp(&(pairs, ranks.map(ace_low)))
             }
-        }
-        if straight {
-            return Some(Hand::Straight(ranks[i + 5 - 1]));
+            (LowHand::DeuceToSeven(hand), LowHand::DeuceToSeven(o_hand)) => o_hand.compare(hand),
+            _ => panic!("Can't compare A-5 and 2-7 low hands"),
         }
     }
-    None
 }
 
-fn is_three_of_a_kind(cards: &[Card]) -> Option<Hand> {
-    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
-    ranks.sort();
-    // separate cards by rank:
-    let mut counts: HashMap<Rank, usize> = HashMap::new();
-    for rank in ranks.iter() {
-        *counts.entry(*rank).or_insert(0) += 1;
-    }
-    for (rank, count) in counts.iter() {
-        if *count == 3 {
-            let mut other = ranks.clone();
-            other.retain(|r| r != rank);
-            other.sort();
-            return Some(Hand::ThreeOfAKind(
-                *rank,
-                other[other.len() - 1],
-                other[other.len() - 2],
-            ));
+// "8-5-4-2-A low", ranks in the same order as the hand.
+// 2-7 lows read like the high hand they are.
+impl fmt::Display for LowHand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LowHand::AceToFive(_, ranks) => {
+                let ranks: Vec<String> = ranks.iter().map(|r| r.to_string()).collect();
+                write!(f, "{} low", ranks.join("-"))
+            }
+            LowHand::DeuceToSeven(hand) => write!(f, "2-7 low, {}", hand),
         }
     }
-    None
 }
 
-fn is_pairs(cards: &[Card]) -> Option<Hand> {
-    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
-    ranks.sort();
-    // separate cards by rank:
-    let mut counts: HashMap<Rank, usize> = HashMap::new();
-    for rank in ranks.iter() {
-        *counts.entry(*rank).or_insert(0) += 1;
+// Rank value with the Ace below the Two
+fn ace_low(rank: Rank) -> u8 {
+    match rank {
+        Rank::Ace => 0,
+        _ => rank as u8 + 1,
     }
-    let mut pairs = Vec::new();
-    for (rank, count) in counts.iter() {
-        if *count == 2 {
-            pairs.push(*rank);
+}
+
+// Best A-5 low out of the cards. With a qualifier, like Eight for 8-or-better,
+// only five different ranks up to the qualifier count.
+pub fn calculate_ace_to_five_low(cards: &[Card], qualifier: Option<Rank>) -> Option<LowHand> {
+    let low = fives(cards.len())
+        .map(|five| ace_to_five(five.map(|i| cards[i])))
+        .max_by(|a, b| a.compare(b))?;
+    match (&low, qualifier) {
+        (LowHand::AceToFive(pairs, ranks), Some(qualifier))
+            if *pairs > 0 || ace_low(ranks[0]) > ace_low(qualifier) =>
+        {
+            None
         }
+        _ => Some(low),
     }
-    // handle 3 pair case, select highest 2 pairs
-    if pairs.len() == 3 {
-        let mut other = ranks.clone();
-        other.retain(|r| r != &pairs[0] && r != &pairs[1] && r != &pairs[2]);
-        other.sort();
-        pairs.sort();
-        if pairs[0] > other[other.len() - 1] {
-            Some(Hand::TwoPair(pairs[2], pairs[1], pairs[0]))
-        } else {
-            Some(Hand::TwoPair(pairs[2], pairs[1], other[other.len() - 1]))
-        }
-    } else if pairs.len() == 2 {
-        let mut other = ranks.clone();
-        other.retain(|r| r != &pairs[0] && r != &pairs[1]);
-        other.sort();
-        pairs.sort();
-        Some(Hand::TwoPair(pairs[1], pairs[0], other[other.len() - 1]))
-    } else if pairs.len() == 1 {
-        let mut other = ranks.clone();
-        other.retain(|r| r != &pairs[0]);
-        other.sort();
-        Some(Hand::Pair(
-            pairs[0],
-            other[other.len() - 1],
-            other[other.len() - 2],
-            other[other.len() - 3],
-        ))
-    } else {
-        None
+}
+
+fn ace_to_five(cards: [Card; HAND_SIZE]) -> LowHand {
+    let mut counts = [0u8; 13];
+    for card in cards.iter() {
+        counts[card.rank as usize] += 1;
+    }
+    let mut ranks = cards.map(|c| c.rank);
+    // pairs first, then high to low
+    ranks.sort_by_key(|&r| std::cmp::Reverse((counts[r as usize], ace_low(r))));
+    let mut groups: Vec<u8> = counts.iter().copied().filter(|&c| c > 0).collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    let pairs = match groups[..] {
+        [4, ..] => 5,
+        [3, 2] => 4,
+        [3, ..] => 3,
+        [2, 2, ..] => 2,
+        [2, ..] => 1,
+        _ => 0,
+    };
+    LowHand::AceToFive(pairs, ranks)
+}
+
+pub fn calculate_deuce_to_seven_low(cards: &[Card]) -> LowHand {
+    fives(cards.len())
+        .map(|five| {
+            let hand = match calculate_hand(&five.map(|i| cards[i])) {
+                // the Ace only plays high
+                Hand::Straight(Rank::Five) => {
+                    Hand::HighCard(Rank::Ace, Rank::Five, Rank::Four, Rank::Three, Rank::Two)
+                }
+                Hand::StraightFlush(Rank::Five) => {
+                    Hand::Flush(Rank::Ace, Rank::Five, Rank::Four, Rank::Three, Rank::Two)
+                }
+                hand => hand,
+            };
+            LowHand::DeuceToSeven(hand)
+        })
+        .max_by(|a, b| a.compare(b))
+        .expect("A low hand needs at least 5 cards")
+}
+
+// Omaha lows also use exactly two hole cards and three board cards
+pub fn calculate_omaha_low(
+    hole: &[Card],
+    board: &[Card],
+    qualifier: Option<Rank>,
+) -> Option<LowHand> {
+    pairs(hole.len())
+        .flat_map(|(i, j)| {
+            triples(board.len()).filter_map(move |(k, l, m)| {
+                let cards = [hole[i], hole[j], board[k], board[l], board[m]];
+                calculate_ace_to_five_low(&cards, qualifier)
+            })
+        })
+        .max_by(|a, b| a.compare(b))
+}
+
+//
+// Rank bitmasks: bit i is set when RANKS[i] is in the set
+//
+type RankMask = u16;
+
+fn rank_bit(rank: Rank) -> RankMask {
+    1 << rank as u8
+}
+
+fn highest(mask: RankMask) -> Rank {
+    RANKS[15 - mask.leading_zeros() as usize]
+}
+
+fn pop_highest(mask: &mut RankMask) -> Rank {
+    let rank = highest(*mask);
+    *mask &= !rank_bit(rank);
+    rank
+}
+
+// Top card of the highest straight in the mask
+fn straight_high(mask: RankMask, wheel: &[Rank; 4]) -> Option<Rank> {
+    // bit i stays set if the five ranks from i up are all there
+    let runs = mask & (mask >> 1) & (mask >> 2) & (mask >> 3) & (mask >> 4);
+    if runs != 0 {
+        return Some(RANKS[15 - runs.leading_zeros() as usize + 4]);
     }
+    // ace low straight, only if there's no higher straight
+    let wheel_mask = wheel
+        .iter()
+        .fold(rank_bit(Rank::Ace), |mask, rank| mask | rank_bit(*rank));
+    if mask & wheel_mask == wheel_mask {
+        return Some(wheel[3]);
+    }
+    None
 }
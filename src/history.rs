@@ -0,0 +1,588 @@
+// history.rs
+use crate::betting::*;
+use crate::events::*;
+use crate::gameset::*;
+use crate::playerinput::*;
+use crate::poker::*;
+use crate::rake::*;
+use crate::variant::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// First line of every hand history file.
+// Bump the version if the format ever changes, old files must stay readable.
+const HEADER: &str = "poker_cli hand history v1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryAction {
+    pub round: Round,
+    pub seat: usize,
+    pub action: Action,
+    // chips the action put in the pot
+    pub amount: u32,
+}
+
+// Everything needed to read back or replay a single hand.
+// Seats are indexes into `seats`, same as in the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandHistory {
+    pub seed: u64,
+    pub config: GameConfig,
+    pub button: usize,
+    // name and stack of each seat when the hand started
+    pub seats: Vec<(String, u32)>,
+    // empty for seats that weren't dealt in
+    pub hole_cards: Vec<Vec<Card>>,
+    // face-up cards by seat, only in stud
+    pub upcards: Vec<Vec<Card>>,
+    pub antes: Vec<(usize, u32)>,
+    pub blinds: Vec<(usize, u32)>,
+    pub straddle: Option<(usize, u32)>,
+    pub bring_in: Option<(usize, u32)>,
+    pub actions: Vec<HistoryAction>,
+    // community cards that were shown
+    pub board: Vec<Card>,
+    // cards of each extra run of the board
+    pub extra_runs: Vec<Vec<Card>>,
+    // seat and chips of the top bet nobody called
+    pub uncalled: Option<(usize, u32)>,
+    pub pots: Vec<Pot>,
+    // pot index, amount taken by the house
+    pub rake: Vec<(usize, u32)>,
+    // pot index, seat, amount
+    pub awards: Vec<(usize, usize, u32)>,
+    // stack of each seat when the hand ended
+    pub stacks: Vec<u32>,
+}
+
+impl HandHistory {
+    pub fn from_game(game: &Game) -> HandHistory {
+        let mut history = HandHistory {
+            seed: game.hand_seed,
+            config: game.config.clone(),
+            button: game.button,
+            seats: Vec::new(),
+            hole_cards: vec![Vec::new(); game.players.len()],
+            upcards: vec![Vec::new(); game.players.len()],
+            antes: Vec::new(),
+            blinds: Vec::new(),
+            straddle: None,
+            bring_in: None,
+            actions: Vec::new(),
+            board: Vec::new(),
+            extra_runs: Vec::new(),
+            uncalled: None,
+            pots: Vec::new(),
+            rake: Vec::new(),
+            awards: Vec::new(),
+            stacks: game.players.iter().map(|p| p.balance).collect(),
+        };
+        let mut round = game.config.variant.first_round();
+        for event in game.events.iter() {
+            match event {
+                GameEvent::HandStarted { seats, .. } => history.seats = seats.clone(),
+                GameEvent::HoleCardsDealt { seat, cards } => {
+                    history.hole_cards[*seat].extend(cards)
+                }
+                GameEvent::UpCardDealt { seat, card } => history.upcards[*seat].push(*card),
+                GameEvent::AntePosted { seat, amount } => history.antes.push((*seat, *amount)),
+                GameEvent::BlindPosted { seat, amount } => history.blinds.push((*seat, *amount)),
+                GameEvent::StraddlePosted { seat, amount } => {
+                    history.straddle = Some((*seat, *amount))
+                }
+                GameEvent::BringInPosted { seat, amount } => {
+                    history.bring_in = Some((*seat, *amount))
+                }
+                GameEvent::ActionTaken {
+                    seat,
+                    action,
+                    amount,
+                } => history.actions.push(HistoryAction {
+                    round,
+                    seat: *seat,
+                    action: *action,
+                    amount: *amount,
+                }),
+                GameEvent::StreetAdvanced {
+                    round: new_round,
+                    cards,
+                } => {
+                    round = *new_round;
+                    history.board.extend(cards);
+                }
+                GameEvent::BoardRun { cards, .. } => history.extra_runs.push(cards.clone()),
+                GameEvent::UncalledReturned { seat, amount } => {
+                    history.uncalled = Some((*seat, *amount))
+                }
+                GameEvent::RakeTaken { pot, amount } => history.rake.push((*pot, *amount)),
+                GameEvent::PotAwarded { pot, seat, amount } => {
+                    history.awards.push((*pot, *seat, *amount))
+                }
+                _ => {}
+            }
+        }
+        if game.ended {
+            history.pots = game.pots();
+        }
+        history
+    }
+
+    pub fn to_text(&self) -> String {
+        let cards = |cards: &[Card]| Cards(cards.to_vec()).to_string();
+        let forced_bets = &self.config.forced_bets;
+        // Settings lines always come in this order, optional ones only when set
+        let mut lines = vec![
+            HEADER.to_string(),
+            format!("seed {}", self.seed),
+            format!("min_bet {}", self.config.min_bet),
+        ];
+        if forced_bets.ante > 0 {
+            lines.push(format!("ante_size {}", forced_bets.ante));
+        }
+        lines.push(format!("variant {}", self.config.variant.name()));
+        lines.push(format!("betting {}", self.config.betting.name()));
+        if forced_bets.big_blind_ante > 0 {
+            lines.push(format!(
+                "big_blind_ante_size {}",
+                forced_bets.big_blind_ante
+            ));
+        }
+        if let Some(straddle) = forced_bets.straddle {
+            lines.push(format!("straddle_rule {}", straddle.name()));
+        }
+        if self.config.runs > 1 {
+            lines.push(format!("runs {}", self.config.runs));
+        }
+        if let Some(rake) = self.config.rake {
+            let mut line = format!("rake_rule {}", rake.percent);
+            if let Some(cap) = rake.cap {
+                line += &format!(" cap {}", cap);
+            }
+            if rake.no_flop_no_drop {
+                line += " noflopnodrop";
+            }
+            lines.push(line);
+        }
+        if self.config.jokers > 0 {
+            lines.push(format!("jokers {}", self.config.jokers));
+        }
+        if !self.config.wild.is_empty() {
+            let ranks: Vec<String> = self.config.wild.iter().map(|r| r.to_string()).collect();
+            lines.push(format!("wild {}", ranks.join(" ")));
+        }
+        lines.push(format!("button {}", self.button));
+        for (seat, (name, stack)) in self.seats.iter().enumerate() {
+            lines.push(format!("seat {} {} {}", seat, stack, name));
+        }
+        for (seat, hole) in self.hole_cards.iter().enumerate() {
+            if !hole.is_empty() {
+                lines.push(format!("hole {} {}", seat, cards(hole)));
+            }
+        }
+        for (seat, up) in self.upcards.iter().enumerate() {
+            if !up.is_empty() {
+                lines.push(format!("up {} {}", seat, cards(up)));
+            }
+        }
+        for (seat, amount) in self.antes.iter() {
+            lines.push(format!("ante {} {}", seat, amount));
+        }
+        for (seat, amount) in self.blinds.iter() {
+            lines.push(format!("blind {} {}", seat, amount));
+        }
+        if let Some((seat, amount)) = self.straddle {
+            lines.push(format!("straddle {} {}", seat, amount));
+        }
+        if let Some((seat, amount)) = self.bring_in {
+            lines.push(format!("bring_in {} {}", seat, amount));
+        }
+        for a in self.actions.iter() {
+            let action = match a.action {
+                Action::Check => "check".to_string(),
+                Action::Call => "call".to_string(),
+                Action::Fold => "fold".to_string(),
+                Action::AllIn => "allin".to_string(),
+                Action::Bet(amount) => format!("bet {}", amount),
+                Action::Raise(to) => format!("raise {}", to),
+            };
+            lines.push(format!(
+                "action {:?} {} {} {}",
+                a.round, a.seat, a.amount, action
+            ));
+        }
+        lines.push(
+            format!("board {}", cards(&self.board))
+                .trim_end()
+                .to_string(),
+        );
+        for run in self.extra_runs.iter() {
+            lines.push(format!("run {}", cards(run)));
+        }
+        if let Some((seat, amount)) = self.uncalled {
+            lines.push(format!("uncalled {} {}", seat, amount));
+        }
+        for pot in self.pots.iter() {
+            let eligible: Vec<String> = pot.eligible.iter().map(|s| s.to_string()).collect();
+            lines.push(format!("pot {} {}", pot.amount, eligible.join(" ")));
+        }
+        for (pot, amount) in self.rake.iter() {
+            lines.push(format!("rake {} {}", pot, amount));
+        }
+        for (pot, seat, amount) in self.awards.iter() {
+            lines.push(format!("award {} {} {}", pot, seat, amount));
+        }
+        for (seat, stack) in self.stacks.iter().enumerate() {
+            lines.push(format!("stack {} {}", seat, stack));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    pub fn parse(text: &str) -> Result<HandHistory, String> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err("Not a hand history file".to_string()),
+        }
+        let mut history = HandHistory {
+            seed: 0,
+            config: GameConfig::new(0),
+            button: 0,
+            seats: Vec::new(),
+            hole_cards: Vec::new(),
+            upcards: Vec::new(),
+            antes: Vec::new(),
+            blinds: Vec::new(),
+            straddle: None,
+            bring_in: None,
+            actions: Vec::new(),
+            board: Vec::new(),
+            extra_runs: Vec::new(),
+            uncalled: None,
+            pots: Vec::new(),
+            rake: Vec::new(),
+            awards: Vec::new(),
+            stacks: Vec::new(),
+        };
+        for (i, line) in lines {
+            let error = |message: &str| format!("Line {}: {} ({})", i + 1, message, line);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number = |index: usize| -> Result<u64, String> {
+                words
+                    .get(index)
+                    .and_then(|w| w.parse::<u64>().ok())
+                    .ok_or_else(|| error("expected a number"))
+            };
+            let cards = |from: usize| -> Result<Vec<Card>, String> {
+                words[from..]
+                    .iter()
+                    .map(|w| w.parse::<Card>().map_err(|e| error(&e)))
+                    .collect()
+            };
+            match words.first().copied() {
+                None => continue,
+                Some("seed") => history.seed = number(1)?,
+                Some("min_bet") => history.config.min_bet = number(1)? as u32,
+                Some("ante_size") => history.config.forced_bets.ante = number(1)? as u32,
+                Some("big_blind_ante_size") => {
+                    history.config.forced_bets.big_blind_ante = number(1)? as u32
+                }
+                Some("straddle_rule") => {
+                    history.config.forced_bets.straddle = Some(
+                        words
+                            .get(1)
+                            .and_then(|name| Straddle::from_name(name))
+                            .ok_or_else(|| error("unknown straddle"))?,
+                    )
+                }
+                // Without this line the hand is Hold'em, like hands saved before variants existed
+                Some("variant") => {
+                    history.config.variant = words
+                        .get(1)
+                        .and_then(|name| Variant::from_name(name))
+                        .ok_or_else(|| error("unknown variant"))?
+                }
+                Some("jokers") => {
+                    history.config.jokers = number(1)? as usize;
+                    if history.config.jokers > JOKERS.len() {
+                        return Err(error("too many jokers"));
+                    }
+                }
+                Some("wild") => {
+                    history.config.wild = words[1..]
+                        .iter()
+                        .map(|w| w.parse::<Rank>().map_err(|e| error(&e)))
+                        .collect::<Result<_, _>>()?
+                }
+                // Without this line the hand is no-limit
+                Some("betting") => {
+                    history.config.betting = words
+                        .get(1)
+                        .and_then(|name| Betting::from_name(name))
+                        .ok_or_else(|| error("unknown betting structure"))?
+                }
+                // rake_rule <percent> [cap <n>] [noflopnodrop]
+                Some("rake_rule") => {
+                    let percent = words
+                        .get(1)
+                        .and_then(|w| w.parse::<f64>().ok())
+                        .filter(|p| (0.0..=100.0).contains(p))
+                        .ok_or_else(|| error("invalid rake percentage"))?;
+                    let mut rake = Rake::new(percent);
+                    let mut i = 2;
+                    while let Some(word) = words.get(i) {
+                        match *word {
+                            "cap" => {
+                                rake.cap = Some(number(i + 1)? as u32);
+                                i += 1;
+                            }
+                            "noflopnodrop" => rake.no_flop_no_drop = true,
+                            _ => return Err(error("invalid rake rule")),
+                        }
+                        i += 1;
+                    }
+                    history.config.rake = Some(rake);
+                }
+                Some("button") => history.button = number(1)? as usize,
+                Some("seat") => {
+                    if number(1)? as usize != history.seats.len() {
+                        return Err(error("seats out of order"));
+                    }
+                    let name = words
+                        .get(3..)
+                        .filter(|name| !name.is_empty())
+                        .ok_or_else(|| error("missing name"))?;
+                    history.seats.push((name.join(" "), number(2)? as u32));
+                    history.hole_cards.push(Vec::new());
+                    history.upcards.push(Vec::new());
+                }
+                Some("hole") => {
+                    let seat = number(1)? as usize;
+                    let hole = cards(2)?;
+                    match history.hole_cards.get_mut(seat) {
+                        Some(cards) => *cards = hole,
+                        None => return Err(error("unknown seat")),
+                    }
+                }
+                Some("up") => {
+                    let seat = number(1)? as usize;
+                    let up = cards(2)?;
+                    match history.upcards.get_mut(seat) {
+                        Some(cards) => *cards = up,
+                        None => return Err(error("unknown seat")),
+                    }
+                }
+                Some("ante") => history.antes.push((number(1)? as usize, number(2)? as u32)),
+                Some("blind") => history
+                    .blinds
+                    .push((number(1)? as usize, number(2)? as u32)),
+                Some("straddle") => {
+                    history.straddle = Some((number(1)? as usize, number(2)? as u32))
+                }
+                Some("bring_in") => {
+                    history.bring_in = Some((number(1)? as usize, number(2)? as u32))
+                }
+                Some("action") => {
+                    let round = words
+                        .get(1)
+                        .and_then(|name| parse_round(name))
+                        .ok_or_else(|| error("invalid round"))?;
+                    let action = match words.get(4).copied() {
+                        Some("check") => Action::Check,
+                        Some("call") => Action::Call,
+                        Some("fold") => Action::Fold,
+                        Some("allin") => Action::AllIn,
+                        Some("bet") => Action::Bet(number(5)? as u32),
+                        Some("raise") => Action::Raise(number(5)? as u32),
+                        _ => return Err(error("invalid action")),
+                    };
+                    history.actions.push(HistoryAction {
+                        round,
+                        seat: number(2)? as usize,
+                        action,
+                        amount: number(3)? as u32,
+                    });
+                }
+                Some("board") => history.board = cards(1)?,
+                Some("run") => history.extra_runs.push(cards(1)?),
+                Some("runs") => history.config.runs = number(1)? as usize,
+                Some("uncalled") => {
+                    history.uncalled = Some((number(1)? as usize, number(2)? as u32))
+                }
+                Some("pot") => history.pots.push(Pot {
+                    amount: number(1)? as u32,
+                    eligible: (2..words.len())
+                        .map(|i| number(i).map(|n| n as usize))
+                        .collect::<Result<_, _>>()?,
+                }),
+                Some("rake") => history.rake.push((number(1)? as usize, number(2)? as u32)),
+                Some("award") => history.awards.push((
+                    number(1)? as usize,
+                    number(2)? as usize,
+                    number(3)? as u32,
+                )),
+                Some("stack") => {
+                    if number(1)? as usize != history.stacks.len() {
+                        return Err(error("stacks out of order"));
+                    }
+                    history.stacks.push(number(2)? as u32)
+                }
+                Some(_) => return Err(error("unknown line")),
+            }
+        }
+        history.config.validate()?;
+        history.check_table()?;
+        Ok(history)
+    }
+
+    // Catches what the engine would panic on, or deal wrong, before replaying
+    fn check_table(&self) -> Result<(), String> {
+        let variant = self.config.variant;
+        let funded = self.seats.iter().filter(|(_, stack)| *stack > 0).count();
+        if funded < 2 {
+            return Err("Not enough seats with chips".to_string());
+        }
+        if funded > variant.max_players() {
+            return Err(format!("Too many players for {}", variant.name()));
+        }
+        match self.seats.get(self.button) {
+            Some((_, stack)) if *stack > 0 => {}
+            _ => {
+                return Err(format!(
+                    "No player with chips on button seat {}",
+                    self.button
+                ))
+            }
+        }
+        self.seats
+            .iter()
+            .try_fold(0u32, |total, (_, stack)| total.checked_add(*stack))
+            .ok_or_else(|| "Too many chips on the table".to_string())?;
+        let mut seen: Vec<Card> = Vec::new();
+        let dealt = self
+            .hole_cards
+            .iter()
+            .chain(self.upcards.iter())
+            .chain(self.extra_runs.iter())
+            .flatten()
+            .chain(self.board.iter());
+        for card in dealt {
+            let in_deck =
+                variant.ranks().contains(&card.rank) || JOKERS[..self.config.jokers].contains(card);
+            if !in_deck {
+                return Err(format!("{} isn't in the deck", card));
+            }
+            if seen.contains(card) {
+                return Err(format!("{} is dealt twice", card));
+            }
+            seen.push(*card);
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &Path) -> Result<HandHistory, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(path)?;
+        Ok(HandHistory::parse(&text)?)
+    }
+}
+
+fn parse_round(name: &str) -> Option<Round> {
+    match name {
+        "PreFlop" => Some(Round::PreFlop),
+        "Flop" => Some(Round::Flop),
+        "Turn" => Some(Round::Turn),
+        "River" => Some(Round::River),
+        "ThirdStreet" => Some(Round::ThirdStreet),
+        "FourthStreet" => Some(Round::FourthStreet),
+        "FifthStreet" => Some(Round::FifthStreet),
+        "SixthStreet" => Some(Round::SixthStreet),
+        "SeventhStreet" => Some(Round::SeventhStreet),
+        _ => None,
+    }
+}
+
+// Plays the recorded actions through the engine again, one by one, and checks
+// that the hand ends the same way. The sinks see the replayed events.
+pub fn replay(history: &HandHistory, sinks: Vec<Box<dyn EventSink>>) -> Result<(), String> {
+    let players = history
+        .seats
+        .iter()
+        .map(|(name, stack)| {
+            let mut player = Player::with_controller(
+                name.clone(),
+                Box::new(ScriptedController::new(Vec::new())),
+            );
+            player.balance = *stack;
+            player
+        })
+        .collect();
+    let mut game = Game::with_cards(
+        players,
+        history.config.clone(),
+        history.button,
+        history.seed,
+        DealtCards {
+            holes: history.hole_cards.clone(),
+            upcards: history.upcards.clone(),
+            // Extra runs are drawn right after the board
+            board: history
+                .board
+                .iter()
+                .chain(history.extra_runs.iter().flatten())
+                .copied()
+                .collect(),
+        },
+    );
+    for sink in sinks {
+        game.subscribe(sink);
+    }
+
+    for (i, recorded) in history.actions.iter().enumerate() {
+        if game.ended {
+            return Err(format!("Action {}: the hand already ended", i + 1));
+        }
+        if game.turn != (recorded.round, recorded.seat) {
+            return Err(format!(
+                "Action {}: recorded {:?} for seat {}, but the engine is at {:?} for seat {}",
+                i + 1,
+                recorded.round,
+                recorded.seat,
+                game.turn.0,
+                game.turn.1
+            ));
+        }
+        game.take_action(recorded.action)
+            .map_err(|message| format!("Action {}: {}", i + 1, message))?;
+    }
+    if !game.ended {
+        return Err("The recorded actions don't finish the hand".to_string());
+    }
+
+    let replayed = game.history();
+    if replayed.rake != history.rake {
+        return Err(format!(
+            "Rake differs: recorded {:?}, replayed {:?}",
+            history.rake, replayed.rake
+        ));
+    }
+    if replayed.awards != history.awards {
+        return Err(format!(
+            "Pots awarded differ: recorded {:?}, replayed {:?}",
+            history.awards, replayed.awards
+        ));
+    }
+    if replayed.stacks != history.stacks {
+        return Err(format!(
+            "Final stacks differ: recorded {:?}, replayed {:?}",
+            history.stacks, replayed.stacks
+        ));
+    }
+    if replayed != *history {
+        return Err("Replayed hand doesn't match the recorded history".to_string());
+    }
+    Ok(())
+}
@@ -1,19 +1,50 @@
 // main.rs
 
+use crate::betting::*;
+use crate::equity::*;
+use crate::events::*;
 use crate::gameset::*;
+use crate::history::*;
+use crate::playerinput::*;
+use crate::poker::*;
+use crate::rake::*;
+use crate::range::*;
+use crate::session::*;
+use crate::variant::*;
 
+#[allow(dead_code)]
+mod bench;
+#[allow(dead_code)]
+mod betting;
+#[allow(dead_code)]
+mod equity;
+#[allow(dead_code)]
+mod events;
 #[allow(dead_code)]
 mod gameset;
 #[allow(dead_code)]
 mod hands;
 #[allow(dead_code)]
+mod history;
+#[allow(dead_code)]
+mod outs;
+#[allow(dead_code)]
 mod playerinput;
 #[allow(dead_code)]
 mod poker;
+#[allow(dead_code)]
+mod rake;
+#[allow(dead_code)]
+mod range;
+#[allow(dead_code)]
+mod session;
+#[allow(dead_code)]
+mod stud;
+#[allow(dead_code)]
+mod variant;
 
 #[allow(dead_code)]
 #[allow(unused_imports)]
-
 fn game_1() {
     let john = Player::new(String::from("John"));
     let man = Player::new("Man".into());
@@ -29,13 +60,15 @@ fn game_1() {
     }
 }
 
-fn game_2() {
-    let mut a = Player::new("A".into());
-    let mut b = Player::new("B".into());
-    let mut c = Player::new("C".into());
+fn game_2(config: GameConfig, seed: Option<u64>, history_dir: Option<String>, hints: bool) {
+    let human =
+        |name: &str| Player::with_controller(name.into(), Box::new(StdinController { hints }));
+    let mut a = human("A");
+    let mut b = human("B");
+    let mut c = human("C");
     let mut d = Player::new("D".into());
-    let mut e = Player::new("E".into());
-    let mut f = Player::new("F".into());
+    let mut e = Player::with_controller("E".into(), Box::new(PassiveBot));
+    let mut f = Player::with_controller("F".into(), Box::new(PassiveBot));
 
     a.balance = 430;
     b.balance = 295;
@@ -46,17 +79,204 @@ fn game_2() {
 
     let players = vec![a, b, c, d, e, f];
 
-    let mut game = Game::new(players, 5, false);
-    while !game.ended {
-        println!("Pot: {}, bet: {}, round: {:?}", game.pot, game.bet, game.turn);
-        game.play_turn();
+    // Always seeded, so any session can be played again
+    let seed = seed.unwrap_or_else(rand::random);
+    println!("Session seed {}, play it again with --seed {}", seed, seed);
+    let mut session = Session::with_seed(players, config, Some(10), seed);
+    session.game.subscribe(Box::new(CliRenderer::new()));
+    session.game.history_dir = history_dir.map(|dir| dir.into());
+    session.play();
+    for player in session.players() {
+        println!("{}: {}$", player.name, player.balance);
+    }
+    if session.game.config.rake.is_some() {
+        println!("House: {}$", session.house_total);
+    }
+}
+
+// Replays a saved hand history and checks the engine ends it the same way
+fn replay_file(path: &str) {
+    let history = match HandHistory::load(path.as_ref()) {
+        Ok(history) => history,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+    match replay(&history, vec![Box::new(CliRenderer::new())]) {
+        Ok(()) => println!("Replay matches the hand history"),
+        Err(error) => {
+            eprintln!("Replay failed: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Prints the equity of each player given after `equity`, as hole cards or a range.
+// e.g. `equity AhKh QsQd --board 2c7d9h` or `equity AhKh "TT+, AQs+"`
+fn print_equity(args: &[String]) {
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let exit = |error: String| -> ! {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    };
+    let cards = |text: &String| Card::parse_list(text).unwrap_or_else(|error| exit(error));
+    // Everything that isn't an option or its value is a player
+    let players: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .skip(2)
+        .filter(|(i, arg)| !arg.starts_with("--") && !args[i - 1].starts_with("--"))
+        .map(|(_, arg)| arg)
+        .collect();
+
+    let board = option("--board").map(cards).unwrap_or_default();
+    let dead = option("--dead").map(cards).unwrap_or_default();
+    let variant = option("--variant").map_or(Variant::Holdem, |name| {
+        Variant::from_name(name).expect("Unknown variant!")
+    });
+    let seed = option("--seed").map_or(0, |seed| seed.parse().expect("Invalid seed!"));
+    let samples = option("--samples").map(|n| n.parse().expect("Invalid number of samples!"));
+
+    // Known hole cards for everyone, or ranges
+    let holes: Option<Vec<Vec<Card>>> = players
+        .iter()
+        .map(|p| {
+            Card::parse_list(p)
+                .ok()
+                .filter(|c| c.len() == variant.hole_size())
+        })
+        .collect();
+    let result = match holes {
+        Some(holes) => {
+            let mut query = EquityQuery::new(holes);
+            query.board = board;
+            query.dead = dead;
+            query.variant = variant;
+            query.seed = seed;
+            query.samples = samples.unwrap_or(query.samples);
+            query.calculate()
+        }
+        None => {
+            let ranges = players
+                .iter()
+                .map(|p| Range::parse(p).unwrap_or_else(|error| exit(error)))
+                .collect();
+            let mut query = RangeEquityQuery::new(ranges);
+            query.board = board;
+            query.dead = dead;
+            query.variant = variant;
+            query.seed = seed;
+            query.samples = samples.unwrap_or(query.samples);
+            query.calculate()
+        }
+    };
+
+    match result {
+        Ok(equity) => {
+            let method = if equity.exhaustive { "all" } else { "random" };
+            println!("{} {} runouts", equity.runouts, method);
+            for (player, result) in players.iter().zip(equity.players.iter()) {
+                println!(
+                    "{}: equity {:.2}%, win {:.2}%, tie {:.2}%",
+                    player, result.equity, result.win, result.tie
+                );
+            }
+        }
+        Err(error) => exit(error),
     }
-    game.print_table();
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+    if args.get(1).map(|arg| arg.as_str()) == Some("replay") {
+        match args.get(2) {
+            Some(path) => replay_file(path),
+            None => eprintln!("Usage: poker_cli replay <hand history file>"),
+        }
+        return;
+    }
+    // equity <hole> <hole>... [--board <cards>] [--dead <cards>]
+    if args.get(1).map(|arg| arg.as_str()) == Some("equity") {
+        print_equity(&args);
+        return;
+    }
+    // bench [hands] times the hand evaluator
+    if args.get(1).map(|arg| arg.as_str()) == Some("bench") {
+        let hands = args
+            .get(2)
+            .map_or(1_000_000, |n| n.parse().expect("Invalid number of hands!"));
+        bench::run(hands);
+        return;
+    }
+    // --seed <n> plays the same seats and cards again, given the session seed printed at startup
+    let seed = option("--seed").map(|seed| seed.parse::<u64>().expect("Invalid seed!"));
+    // --history <dir> saves every hand played
+    let history_dir = option("--history");
+    // --variant <name> picks the game, Hold'em by default
+    let mut config = GameConfig::new(5);
+    if let Some(name) = option("--variant") {
+        config.variant = Variant::from_name(&name).expect("Unknown variant!");
+    }
+    // --betting <nl|pl|fl> picks no-limit, pot-limit or fixed-limit, no-limit by default
+    if let Some(name) = option("--betting") {
+        config.betting = Betting::from_name(&name).expect("Unknown betting structure!");
+    }
+    // --ante <n> makes every player pay n$ before each deal
+    if let Some(ante) = option("--ante") {
+        config.forced_bets.ante = ante.parse::<u32>().expect("Invalid ante!");
+    }
+    // --bb-ante <n> makes the big blind pay n$ for the whole table instead
+    if let Some(ante) = option("--bb-ante") {
+        config.forced_bets.big_blind_ante = ante.parse::<u32>().expect("Invalid big blind ante!");
+    }
+    // --straddle <utg|button> posts a straddle of twice the big blind every hand
+    if let Some(name) = option("--straddle") {
+        config.forced_bets.straddle = Some(Straddle::from_name(&name).expect("Unknown straddle!"));
+    }
+    // --runs <n> deals the rest of the board n times when everyone is all-in
+    if let Some(runs) = option("--runs") {
+        config.runs = runs.parse::<usize>().expect("Invalid number of runs!");
+        assert!(config.runs >= 1, "At least one run!");
+    }
+    // --rake <percent> takes a cut of every pot, --rake-cap <n> caps it per hand and
+    // --no-flop-no-drop skips hands that end before the flop
+    if let Some(percent) = option("--rake") {
+        let mut rake = Rake::new(percent.parse::<f64>().expect("Invalid rake!"));
+        assert!(
+            (0.0..=100.0).contains(&rake.percent),
+            "Rake must be a percentage!"
+        );
+        rake.cap = option("--rake-cap").map(|cap| cap.parse::<u32>().expect("Invalid cap!"));
+        rake.no_flop_no_drop = args.iter().any(|arg| arg == "--no-flop-no-drop");
+        config.rake = Some(rake);
+    }
+    // --jokers <n> adds up to 2 jokers to the deck, --wild <ranks> makes ranks wild, e.g. 2
+    if let Some(jokers) = option("--jokers") {
+        config.jokers = jokers.parse::<usize>().expect("Invalid number of jokers!");
+        assert!(config.jokers <= JOKERS.len(), "At most 2 jokers!");
+    }
+    if let Some(ranks) = option("--wild") {
+        config.wild = ranks
+            .split(',')
+            .map(|rank| rank.parse::<Rank>().expect("Invalid wild rank!"))
+            .collect();
+    }
+    config.validate().expect("Invalid game settings!");
     // game_1();
-    game_2();
+    // --hints shows the outs on the flop and turn
+    let hints = args.iter().any(|arg| arg == "--hints");
+    game_2(config, seed, history_dir, hints);
 }
 
 #[cfg(test)]
@@ -0,0 +1,200 @@
+// outs.rs
+use crate::hands::*;
+use crate::poker::*;
+use crate::variant::*;
+use std::cmp::Ordering::Greater;
+
+// What an out completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Draw {
+    Flush,
+    OpenEnded,
+    Gutshot,
+    Set,
+    Pair,
+    // any other better hand: two pair, trips, full house...
+    Other,
+    // Doesn't improve the hand yet, but gives a flush or straight draw for the river
+    Backdoor,
+}
+
+pub const DRAWS: [Draw; 7] = [
+    Draw::Flush,
+    Draw::OpenEnded,
+    Draw::Gutshot,
+    Draw::Set,
+    Draw::Pair,
+    Draw::Other,
+    Draw::Backdoor,
+];
+
+impl Draw {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Draw::Flush => "flush draw",
+            Draw::OpenEnded => "open-ended straight draw",
+            Draw::Gutshot => "gutshot",
+            Draw::Set => "set",
+            Draw::Pair => "pair",
+            Draw::Other => "better hand",
+            Draw::Backdoor => "backdoor",
+        }
+    }
+}
+
+// An unseen card and the hand it makes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Out {
+    pub card: Card,
+    pub hand: Hand,
+    pub draw: Draw,
+}
+
+// Unseen cards that improve the player on the next street, on a flop or turn board.
+// Without opponents an out makes a better hand category, by more than it helps the board:
+// pairing the board doesn't count, a set does.
+// With known opponent hands, an out takes the lead from all of them.
+pub fn find_outs(
+    variant: Variant,
+    hole: &[Card],
+    board: &[Card],
+    opponents: &[Vec<Card>],
+) -> Vec<Out> {
+    if board.len() < 3 || board.len() >= variant.table_size() {
+        return Vec::new();
+    }
+    let seen: Vec<Card> = hole
+        .iter()
+        .chain(board.iter())
+        .chain(opponents.iter().flatten())
+        .copied()
+        .collect();
+    let unseen: Vec<Card> = SUITS
+        .iter()
+        .flat_map(|&suit| variant.ranks().iter().map(move |&rank| Card { rank, suit }))
+        .filter(|card| !seen.contains(card))
+        .collect();
+    let with = |board: &[Card], card: Card| -> Vec<Card> {
+        board.iter().copied().chain(std::iter::once(card)).collect()
+    };
+    let leads = |board: &[Card]| {
+        let hand = variant.hand(hole, board);
+        opponents
+            .iter()
+            .all(|o| variant.compare_hands(&hand, &variant.hand(o, board)) == Greater)
+    };
+
+    let current = variant.hand(hole, board);
+    let lead = current.category() as i8 - board_category(variant, board) as i8;
+    let improving: Vec<(Card, Hand)> = unseen
+        .iter()
+        .filter_map(|&card| {
+            let next = with(board, card);
+            let hand = variant.hand(hole, &next);
+            let improves = if opponents.is_empty() {
+                hand.category() > current.category()
+                    && hand.category() as i8 - board_category(variant, &next) as i8 > lead
+            } else {
+                !leads(board) && leads(&next)
+            };
+            improves.then_some((card, hand))
+        })
+        .collect();
+
+    // Two or more ranks that make a straight is open-ended (or a double gutshot)
+    let mut straight_ranks: Vec<Rank> = improving
+        .iter()
+        .filter(|(_, hand)| matches!(hand, Hand::Straight(_)))
+        .map(|(card, _)| card.rank)
+        .collect();
+    straight_ranks.sort();
+    straight_ranks.dedup();
+    let mut outs: Vec<Out> = improving
+        .iter()
+        .map(|(card, hand)| {
+            let draw = match hand {
+                Hand::Flush(..) | Hand::StraightFlush(_) | Hand::RoyalFlush => Draw::Flush,
+                Hand::Straight(_) if straight_ranks.len() >= 2 => Draw::OpenEnded,
+                Hand::Straight(_) => Draw::Gutshot,
+                Hand::ThreeOfAKind(rank, ..)
+                    if hole.iter().filter(|c| c.rank == *rank).count() >= 2 =>
+                {
+                    Draw::Set
+                }
+                Hand::Pair(..) => Draw::Pair,
+                _ => Draw::Other,
+            };
+            Out {
+                card: *card,
+                hand: hand.clone(),
+                draw,
+            }
+        })
+        .collect();
+
+    // Backdoor draws need two more cards, so only on the flop
+    if board.len() + 2 == variant.table_size() {
+        let (flush_draw, straight_draw) = draws(variant, hole, board, &unseen);
+        for &card in unseen.iter() {
+            if outs.iter().any(|o| o.card == card) {
+                continue;
+            }
+            let next = with(board, card);
+            let (flush, straight) = draws(variant, hole, &next, &unseen);
+            if (flush && !flush_draw) || (straight && !straight_draw) {
+                outs.push(Out {
+                    card,
+                    hand: variant.hand(hole, &next),
+                    draw: Draw::Backdoor,
+                });
+            }
+        }
+    }
+    outs
+}
+
+// Whether one more card can give the player a flush or a straight of their own
+fn draws(variant: Variant, hole: &[Card], board: &[Card], unseen: &[Card]) -> (bool, bool) {
+    let (mut flush, mut straight) = (false, false);
+    for card in unseen.iter().filter(|c| !board.contains(c)) {
+        let next: Vec<Card> = board
+            .iter()
+            .copied()
+            .chain(std::iter::once(*card))
+            .collect();
+        let hand = variant.hand(hole, &next);
+        if hand.category() <= board_category(variant, &next) {
+            continue;
+        }
+        match hand {
+            Hand::Flush(..) => flush = true,
+            Hand::Straight(_) => straight = true,
+            Hand::StraightFlush(_) | Hand::RoyalFlush => (flush, straight) = (true, true),
+            _ => {}
+        }
+    }
+    (flush, straight)
+}
+
+// Category the board makes on its own, so cards that only help the board aren't outs
+fn board_category(variant: Variant, board: &[Card]) -> u8 {
+    // Omaha hands always use two hole cards
+    if variant.is_omaha() {
+        return 0;
+    }
+    if board.len() >= HAND_SIZE {
+        return variant.hand(&[], board).category();
+    }
+    let mut counts = [0; 13];
+    for card in board.iter() {
+        counts[card.rank as usize] += 1;
+    }
+    let pairs = counts.iter().filter(|&&c| c == 2).count();
+    match counts.iter().max() {
+        Some(4) => 7,
+        Some(3) => 3,
+        _ if pairs >= 2 => 2,
+        _ if pairs == 1 => 1,
+        _ => 0,
+    }
+}
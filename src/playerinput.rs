@@ -1,31 +1,197 @@
 // playerinput.rs
 
+use crate::outs::*;
+use crate::poker::*;
+use crate::variant::*;
+use std::collections::VecDeque;
 use std::io;
 
+// Bet(amount): the first bet on a street
+// Raise(amount): the player's total bet after raising, not the difference
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Action {
     Check,
+    Bet(u32),
     Raise(u32),
     Call,
+    AllIn,
     Fold,
 }
 
-pub fn get_action() -> Result<Action, Box<dyn std::error::Error>> {
+pub fn get_action(bet: u32) -> Result<Action, Box<dyn std::error::Error>> {
     let mut buffer = String::new();
     io::stdin().read_line(&mut buffer)?;
     buffer = buffer.trim().to_lowercase();
-    match buffer.as_str() {
-        "check" => Ok(Action::Check),
-        "call" => Ok(Action::Call),
-        "fold" => Ok(Action::Fold),
-        "raise" => {
-            println!("How much?");
-            let mut buffer = String::new();
-            io::stdin().read_line(&mut buffer)?;
-            let amount = buffer.trim().parse::<u32>()?;
-            Ok(Action::Raise(amount))
+    if buffer == "bet" || buffer == "raise" {
+        println!("How much?");
+        let mut amount = String::new();
+        io::stdin().read_line(&mut amount)?;
+        buffer = format!("{} {}", buffer, amount.trim().to_lowercase());
+    }
+    Ok(parse_action(&buffer, bet)?)
+}
+
+// Reads an action typed by a player, facing the given bet.
+// "raise 40" and "raise to 40" make the total bet 40$,
+// "raise by 40" makes it 40$ more than the current bet.
+pub fn parse_action(text: &str, bet: u32) -> Result<Action, String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let amount = |word: &str| {
+        word.trim_end_matches('$')
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid amount '{}'", word))
+    };
+    match words[..] {
+        ["check"] => Ok(Action::Check),
+        ["call"] => Ok(Action::Call),
+        ["fold"] => Ok(Action::Fold),
+        ["all-in"] | ["allin"] | ["all", "in"] => Ok(Action::AllIn),
+        ["bet", to] => Ok(Action::Bet(amount(to)?)),
+        ["raise", to] | ["raise", "to", to] => Ok(Action::Raise(amount(to)?)),
+        ["raise", "by", by] => bet
+            .checked_add(amount(by)?)
+            .map(Action::Raise)
+            .ok_or_else(|| format!("Invalid amount '{}'", by)),
+        _ => Err("Invalid action".to_string()),
+    }
+}
+
+// What a seat can see of the other players
+#[derive(Debug, Clone)]
+pub struct SeatView {
+    pub name: String,
+    pub upcards: Vec<Card>,
+    pub balance: u32,
+    pub bet: u32,
+    pub folded: bool,
+    pub all_in: bool,
+}
+
+// Everything a player is allowed to know when it's their turn
+#[derive(Debug, Clone)]
+pub struct GameView {
+    pub seat: usize,
+    pub variant: Variant,
+    // jokers or wild ranks are in play
+    pub wild: bool,
+    pub hole: Vec<Card>,
+    pub table: Vec<Card>,
+    pub round: Round,
+    pub pot: u32,
+    pub bet: u32,
+    // smallest and largest amount to raise to, None when raising isn't allowed
+    pub raise_range: Option<(u32, u32)>,
+    pub player_bet: u32,
+    pub balance: u32,
+    pub button: usize,
+    pub seats: Vec<SeatView>,
+}
+
+impl GameView {
+    pub fn to_call(&self) -> u32 {
+        self.bet.saturating_sub(self.player_bet)
+    }
+}
+
+// Decides the actions of a seat
+pub trait PlayerController: std::fmt::Debug {
+    fn act(&mut self, view: &GameView) -> Action;
+
+    // Called when the game rejects the last action, before asking again
+    fn illegal_action(&mut self, _reason: &str) {}
+}
+
+// A human at the terminal.
+// With hints, the outs for the next card are shown on the flop and turn,
+// except with wild cards.
+#[derive(Debug)]
+pub struct StdinController {
+    pub hints: bool,
+}
+
+impl StdinController {
+    fn print_outs(&self, view: &GameView) {
+        let outs = find_outs(view.variant, &view.hole, &view.table, &[]);
+        let count = outs.iter().filter(|o| o.draw != Draw::Backdoor).count();
+        if outs.is_empty() {
+            return;
+        }
+        println!("Outs: {}", count);
+        for draw in DRAWS {
+            let cards: Vec<String> = outs
+                .iter()
+                .filter(|o| o.draw == draw)
+                .map(|o| format!("{:+}", o.card))
+                .collect();
+            if !cards.is_empty() {
+                println!("  {}: {}", draw.name(), cards.join(" "));
+            }
         }
-        _ => Err("Invalid action".into()),
     }
 }
 
+impl PlayerController for StdinController {
+    fn act(&mut self, view: &GameView) -> Action {
+        println!("Your cards: {:+}", Cards(view.hole.clone()));
+        if self.hints && !view.wild {
+            self.print_outs(view);
+        }
+        println!("To call: {}$, balance: {}$", view.to_call(), view.balance);
+        let verb = if view.bet == 0 { "bet" } else { "raise to" };
+        match view.raise_range {
+            Some((min, max)) if min == max => println!("You can {} {}$", verb, min),
+            Some((min, max)) => println!("You can {} {}$ up to {}$", verb, min, max),
+            None => println!("You can't raise"),
+        }
+        loop {
+            match get_action(view.bet) {
+                Ok(action) => return action,
+                Err(error) => println!("{}!", error),
+            }
+        }
+    }
+
+    fn illegal_action(&mut self, reason: &str) {
+        println!("{}", reason);
+    }
+}
+
+// Plays a fixed list of actions, for tests and replays
+#[derive(Debug)]
+pub struct ScriptedController {
+    actions: VecDeque<Action>,
+}
+
+impl ScriptedController {
+    pub fn new(actions: Vec<Action>) -> ScriptedController {
+        ScriptedController {
+            actions: actions.into(),
+        }
+    }
+}
+
+impl PlayerController for ScriptedController {
+    fn act(&mut self, _view: &GameView) -> Action {
+        self.actions
+            .pop_front()
+            .expect("Script ran out of actions!")
+    }
+
+    fn illegal_action(&mut self, reason: &str) {
+        panic!("Scripted action rejected: {}", reason);
+    }
+}
+
+// Never bets, checks when it can and calls otherwise
+#[derive(Debug)]
+pub struct PassiveBot;
 
+impl PlayerController for PassiveBot {
+    fn act(&mut self, view: &GameView) -> Action {
+        if view.to_call() == 0 {
+            Action::Check
+        } else {
+            Action::Call
+        }
+    }
+}
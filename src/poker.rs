@@ -1,5 +1,7 @@
 // poker.rs
 use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
 
 // Hole: The 2 cards that each player holds
 // Table: The 5 community cards that are shared by all players
@@ -25,6 +27,17 @@ pub const RANKS: [Rank; 13] = [
     Rank::Ace,
 ];
 pub const SUITS: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+// The black and the red joker, added to the deck in games with jokers
+pub const JOKERS: [Card; 2] = [
+    Card {
+        rank: Rank::Joker,
+        suit: Suit::Spades,
+    },
+    Card {
+        rank: Rank::Joker,
+        suit: Suit::Hearts,
+    },
+];
 
 #[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum Rank {
@@ -41,6 +54,8 @@ pub enum Rank {
     Queen,
     King,
     Ace,
+    // Not in RANKS, it's always wild and never part of a hand
+    Joker,
 }
 
 #[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
@@ -51,12 +66,18 @@ pub enum Suit {
     Clubs,
 }
 
-#[derive(Debug, PartialEq)]
+// Betting rounds. Stud has its own five streets instead of PreFlop to River.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Round {
     PreFlop,
     Flop,
     Turn,
     River,
+    ThirdStreet,
+    FourthStreet,
+    FifthStreet,
+    SixthStreet,
+    SeventhStreet,
     Showdown,
 }
 
@@ -67,13 +88,18 @@ impl Round {
             Round::Flop => Round::Turn,
             Round::Turn => Round::River,
             Round::River => Round::Showdown,
+            Round::ThirdStreet => Round::FourthStreet,
+            Round::FourthStreet => Round::FifthStreet,
+            Round::FifthStreet => Round::SixthStreet,
+            Round::SixthStreet => Round::SeventhStreet,
+            Round::SeventhStreet => Round::Showdown,
             Round::Showdown => panic!("Tried to continue when game is over!"),
         };
         *self = new_round;
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Card {
     pub rank: Rank,
     pub suit: Suit,
@@ -84,3 +110,319 @@ impl Card {
         self.rank.cmp(&other.rank)
     }
 }
+
+// Card notation.
+// Short: "Ah" for the Ace of Hearts, "Tc" or "10c" for the Ten of Clubs.
+// Long: "Ace of Hearts", printed with {:#}.
+// Unicode: "A♥" with suit symbols, printed with {:+}, or a single playing card
+// character like '🂱'.
+// Jokers are "Xs" and "Xh", or '🃏' and '🂿'.
+impl Rank {
+    pub fn to_char(self) -> char {
+        match self {
+            Rank::Two => '2',
+            Rank::Three => '3',
+            Rank::Four => '4',
+            Rank::Five => '5',
+            Rank::Six => '6',
+            Rank::Seven => '7',
+            Rank::Eight => '8',
+            Rank::Nine => '9',
+            Rank::Ten => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Ace => 'A',
+            Rank::Joker => 'X',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        RANKS
+            .iter()
+            .copied()
+            .find(|r| r.to_char() == c.to_ascii_uppercase())
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rank::Two => "Two",
+            Rank::Three => "Three",
+            Rank::Four => "Four",
+            Rank::Five => "Five",
+            Rank::Six => "Six",
+            Rank::Seven => "Seven",
+            Rank::Eight => "Eight",
+            Rank::Nine => "Nine",
+            Rank::Ten => "Ten",
+            Rank::Jack => "Jack",
+            Rank::Queen => "Queen",
+            Rank::King => "King",
+            Rank::Ace => "Ace",
+            Rank::Joker => "Joker",
+        }
+    }
+}
+
+impl Suit {
+    pub fn to_char(self) -> char {
+        match self {
+            Suit::Spades => 's',
+            Suit::Hearts => 'h',
+            Suit::Diamonds => 'd',
+            Suit::Clubs => 'c',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Suit> {
+        SUITS.iter().copied().find(|s| {
+            s.to_char() == c.to_ascii_lowercase() || s.symbol() == c || s.outline_symbol() == c
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Suit::Spades => "Spades",
+            Suit::Hearts => "Hearts",
+            Suit::Diamonds => "Diamonds",
+            Suit::Clubs => "Clubs",
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Suit::Spades => '♠',
+            Suit::Hearts => '♥',
+            Suit::Diamonds => '♦',
+            Suit::Clubs => '♣',
+        }
+    }
+
+    fn outline_symbol(self) -> char {
+        match self {
+            Suit::Spades => '♤',
+            Suit::Hearts => '♡',
+            Suit::Diamonds => '♢',
+            Suit::Clubs => '♧',
+        }
+    }
+}
+
+impl Card {
+    // The Unicode playing card, e.g. '🂱' for the Ace of Hearts
+    pub fn playing_card(&self) -> char {
+        let suit_base = match self.suit {
+            Suit::Spades => 0x1F0A0,
+            Suit::Hearts => 0x1F0B0,
+            Suit::Diamonds => 0x1F0C0,
+            Suit::Clubs => 0x1F0D0,
+        };
+        if self.rank == Rank::Joker {
+            return match self.suit {
+                Suit::Spades => '🃏',
+                Suit::Hearts => '🂿',
+                Suit::Diamonds | Suit::Clubs => '🃟',
+            };
+        }
+        // Aces come first, and there's a Knight between the Jack and the Queen
+        let offset = match self.rank {
+            Rank::Ace => 1,
+            Rank::Queen | Rank::King => self.rank as u32 + 3,
+            _ => self.rank as u32 + 2,
+        };
+        char::from_u32(suit_base + offset).unwrap()
+    }
+
+    pub fn from_playing_card(c: char) -> Option<Card> {
+        SUITS
+            .iter()
+            .flat_map(|&suit| RANKS.iter().map(move |&rank| Card { rank, suit }))
+            .chain(JOKERS)
+            .find(|card| card.playing_card() == c)
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{}", self.name())
+        } else {
+            write!(f, "{}", self.to_char())
+        }
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{}", self.name())
+        } else if f.sign_plus() {
+            write!(f, "{}", self.symbol())
+        } else {
+            write!(f, "{}", self.to_char())
+        }
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#} of {:#}", self.rank, self.suit)
+        } else if f.sign_plus() {
+            write!(f, "{}{:+}", self.rank, self.suit)
+        } else {
+            write!(f, "{}{}", self.rank, self.suit)
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    // "A", "a", "Ace", "T" or "10", "X" or "Joker" for jokers
+    fn from_str(text: &str) -> Result<Rank, String> {
+        let text = text.trim();
+        RANKS
+            .iter()
+            .copied()
+            .chain([Rank::Joker])
+            .find(|r| {
+                text.eq_ignore_ascii_case(&r.to_char().to_string())
+                    || text.eq_ignore_ascii_case(r.name())
+                    || (*r == Rank::Ten && text == "10")
+            })
+            .ok_or_else(|| format!("unknown rank '{}'", text))
+    }
+}
+
+impl FromStr for Suit {
+    type Err = String;
+
+    // "h", "H", "♥", "♡", "Hearts" or "heart"
+    fn from_str(text: &str) -> Result<Suit, String> {
+        let text = text.trim();
+        let mut chars = text.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if let Some(suit) = Suit::from_char(c) {
+                return Ok(suit);
+            }
+        }
+        SUITS
+            .iter()
+            .copied()
+            .find(|s| {
+                text.eq_ignore_ascii_case(s.name())
+                    || text.eq_ignore_ascii_case(&s.name()[..s.name().len() - 1])
+            })
+            .ok_or_else(|| format!("unknown suit '{}'", text))
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    // "Ah", "10h", "A♥", "Ace of Hearts" or "🂱"
+    fn from_str(text: &str) -> Result<Card, String> {
+        let text = text.trim();
+        let error = |reason: String| format!("Invalid card '{}': {}", text, reason);
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => return Err("Invalid card: empty".to_string()),
+            (Some(c), None) => {
+                return Card::from_playing_card(c).ok_or_else(|| error("too short".to_string()))
+            }
+            _ => {}
+        }
+        // ASCII lowercase keeps byte offsets the same as in text
+        let lower = text.to_ascii_lowercase();
+        let (rank, suit) = match lower.find(" of ") {
+            Some(i) => (&text[..i], &text[i + 4..]),
+            None => {
+                // the suit is the last character
+                let split = text.char_indices().last().unwrap().0;
+                (&text[..split], &text[split..])
+            }
+        };
+        Ok(Card {
+            rank: rank.parse().map_err(error)?,
+            suit: suit.parse().map_err(error)?,
+        })
+    }
+}
+
+// A list of cards, printed as "Ah Kd", with {:#} as "Ace of Hearts, King of Diamonds"
+// or with {:+} as "A♥ K♦"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cards(pub Vec<Card>);
+
+impl fmt::Display for Cards {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = if f.alternate() { ", " } else { " " };
+        for (i, card) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", separator)?;
+            }
+            if f.alternate() {
+                write!(f, "{:#}", card)?;
+            } else if f.sign_plus() {
+                write!(f, "{:+}", card)?;
+            } else {
+                write!(f, "{}", card)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cards {
+    type Err = String;
+
+    // Cards separated by spaces or commas, short ones can also go together: "AhKd",
+    // "Ah Kd", "A♥,K♦", "🂱🃎" or "Ace of Hearts, King of Diamonds"
+    fn from_str(text: &str) -> Result<Cards, String> {
+        let mut cards = Vec::new();
+        for part in text.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            if part.to_ascii_lowercase().contains(" of ") {
+                cards.push(part.parse()?);
+                continue;
+            }
+            for word in part.split_whitespace() {
+                for code in split_codes(word) {
+                    cards.push(code.parse()?);
+                }
+            }
+        }
+        Ok(Cards(cards))
+    }
+}
+
+// Splits cards written together like "AhKd" or "10h9h"
+fn split_codes(word: &str) -> Vec<&str> {
+    let mut codes = Vec::new();
+    let mut start = 0;
+    let mut chars = word.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = if Card::from_playing_card(c).is_some() {
+            i + c.len_utf8()
+        } else {
+            // rank, "10" takes two characters, then the suit
+            if c == '1' && chars.peek().map(|&(_, c)| c) == Some('0') {
+                chars.next();
+            }
+            match chars.next() {
+                Some((j, suit)) => j + suit.len_utf8(),
+                None => word.len(),
+            }
+        };
+        codes.push(&word[start..end]);
+        start = end;
+    }
+    codes
+}
+
+impl Card {
+    // Same as parsing Cards, as a Vec
+    pub fn parse_list(text: &str) -> Result<Vec<Card>, String> {
+        text.parse::<Cards>().map(|cards| cards.0)
+    }
+}
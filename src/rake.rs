@@ -0,0 +1,32 @@
+// rake.rs
+
+// The house's cut of every pot, side pots included.
+// percent: share of each pot taken, rounded down
+// cap: most the house takes from a single hand
+// no_flop_no_drop: hands that end in the first betting round aren't raked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rake {
+    pub percent: f64,
+    pub cap: Option<u32>,
+    pub no_flop_no_drop: bool,
+}
+
+impl Rake {
+    pub fn new(percent: f64) -> Rake {
+        Rake {
+            percent,
+            cap: None,
+            no_flop_no_drop: false,
+        }
+    }
+
+    // Rake for a pot, given what was already taken from the hand's other pots
+    pub fn take(&self, pot: u32, taken: u32) -> u32 {
+        let rake = (pot as f64 * self.percent / 100.0).floor() as u32;
+        match self.cap {
+            Some(cap) => rake.min(cap.saturating_sub(taken)),
+            None => rake,
+        }
+        .min(pot)
+    }
+}
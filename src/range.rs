@@ -0,0 +1,222 @@
+// range.rs
+use crate::poker::*;
+
+// Two hole cards and how often they're in the range, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Combo {
+    pub cards: [Card; 2],
+    pub weight: f64,
+}
+
+impl Combo {
+    pub fn uses_any(&self, cards: &[Card]) -> bool {
+        self.cards.iter().any(|c| cards.contains(c))
+    }
+}
+
+// Hole card combos a player can have, e.g. "AKs, TT+, A2s-A5s, 76s-54s, KQo".
+// Each part can end in a weight, "AKo:0.5" keeps half the AKo combos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub combos: Vec<Combo>,
+}
+
+// Two ranks, higher first, and whether they must be suited
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Suitedness {
+    Suited,
+    Offsuit,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Class {
+    high: Rank,
+    low: Rank,
+    suited: Suitedness,
+}
+
+impl Range {
+    pub fn parse(text: &str) -> Result<Range, String> {
+        let mut range = Range { combos: Vec::new() };
+        for part in text.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let (hands, weight) = match part.split_once(':') {
+                Some((hands, weight)) => {
+                    let weight = weight
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|w| (0.0..=1.0).contains(w))
+                        .ok_or_else(|| format!("Invalid weight: {}", part))?;
+                    (hands, weight)
+                }
+                None => (part, 1.0),
+            };
+            for cards in parse_part(hands)? {
+                range.add(Combo { cards, weight });
+            }
+        }
+        if range.combos.is_empty() {
+            return Err("Empty range".to_string());
+        }
+        Ok(range)
+    }
+
+    // A range with only these hole cards
+    pub fn from_hand(cards: [Card; 2]) -> Range {
+        Range {
+            combos: vec![Combo { cards, weight: 1.0 }],
+        }
+    }
+
+    // Adds the combo, replacing its weight if it's already in the range
+    pub fn add(&mut self, combo: Combo) {
+        let same =
+            |c: &Combo| c.cards == combo.cards || c.cards == [combo.cards[1], combo.cards[0]];
+        match self.combos.iter_mut().find(|c| same(c)) {
+            Some(existing) => existing.weight = combo.weight,
+            None => self.combos.push(combo),
+        }
+    }
+
+    // Combos that don't use any of the known cards
+    pub fn without(&self, known: &[Card]) -> Vec<Combo> {
+        self.combos
+            .iter()
+            .filter(|c| c.weight > 0.0 && !c.uses_any(known))
+            .copied()
+            .collect()
+    }
+}
+
+fn parse_part(part: &str) -> Result<Vec<[Card; 2]>, String> {
+    let error = || format!("Invalid range: {}", part);
+    // exact hole cards, e.g. "AhKh"
+    if let Ok(cards) = Card::parse_list(part) {
+        return match cards[..] {
+            [first, second] if first != second => Ok(vec![[first, second]]),
+            _ => Err(error()),
+        };
+    }
+    let classes = if let Some((from, to)) = part.split_once('-') {
+        let (from, to) = (
+            parse_class(from).ok_or_else(error)?,
+            parse_class(to).ok_or_else(error)?,
+        );
+        class_span(from, to).ok_or_else(error)?
+    } else if let Some(class) = part.strip_suffix('+') {
+        let class = parse_class(class).ok_or_else(error)?;
+        if class.high == class.low {
+            // TT+: every pair from Tens up
+            class_span(
+                class,
+                Class {
+                    high: Rank::Ace,
+                    low: Rank::Ace,
+                    ..class
+                },
+            )
+            .unwrap()
+        } else {
+            // ATs+: the kicker goes up to just below the top card
+            let top = RANKS[class.high as usize - 1];
+            class_span(class, Class { low: top, ..class }).unwrap()
+        }
+    } else {
+        vec![parse_class(part).ok_or_else(error)?]
+    };
+    Ok(classes.into_iter().flat_map(expand).collect())
+}
+
+// "AK", "AKs", "AKo" or "TT"
+fn parse_class(text: &str) -> Option<Class> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    let (first, second) = (
+        Rank::from_char(*chars.first()?)?,
+        Rank::from_char(*chars.get(1)?)?,
+    );
+    let suited = match chars.get(2).map(|c| c.to_ascii_lowercase()) {
+        None => Suitedness::Any,
+        Some('s') => Suitedness::Suited,
+        Some('o') => Suitedness::Offsuit,
+        _ => return None,
+    };
+    if chars.len() > 3 || (first == second && suited == Suitedness::Suited) {
+        return None;
+    }
+    Some(Class {
+        high: first.max(second),
+        low: first.min(second),
+        suited,
+    })
+}
+
+// Every class between the two, in either order: pairs (TT-77), the same top card
+// with a range of kickers (A2s-A5s), or the same gap between the cards (76s-54s)
+fn class_span(from: Class, to: Class) -> Option<Vec<Class>> {
+    if from.suited != to.suited {
+        return None;
+    }
+    let (from, to) = if from.low <= to.low {
+        (from, to)
+    } else {
+        (to, from)
+    };
+    let gap = |c: Class| c.high as usize - c.low as usize;
+    let shift = to.low as usize - from.low as usize;
+    if from.high == to.high && from.high != from.low {
+        // same top card, the kicker moves
+        return Some(
+            (from.low as usize..=to.low as usize)
+                .map(|low| Class {
+                    low: RANKS[low],
+                    ..from
+                })
+                .collect(),
+        );
+    }
+    if gap(from) == gap(to) {
+        // both cards move together, this covers pairs too
+        return Some(
+            (0..=shift)
+                .map(|i| Class {
+                    high: RANKS[from.high as usize + i],
+                    low: RANKS[from.low as usize + i],
+                    suited: from.suited,
+                })
+                .collect(),
+        );
+    }
+    None
+}
+
+// Every pair of cards in the class
+fn expand(class: Class) -> Vec<[Card; 2]> {
+    let mut combos = Vec::new();
+    for (i, &suit_1) in SUITS.iter().enumerate() {
+        for (j, &suit_2) in SUITS.iter().enumerate() {
+            // pairs need different suits, and each only once
+            if class.high == class.low && j <= i {
+                continue;
+            }
+            let suited = suit_1 == suit_2;
+            let wanted = match class.suited {
+                Suitedness::Suited => suited,
+                Suitedness::Offsuit => !suited,
+                Suitedness::Any => true,
+            };
+            if wanted {
+                combos.push([
+                    Card {
+                        rank: class.high,
+                        suit: suit_1,
+                    },
+                    Card {
+                        rank: class.low,
+                        suit: suit_2,
+                    },
+                ]);
+            }
+        }
+    }
+    combos
+}
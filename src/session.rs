@@ -0,0 +1,116 @@
+// session.rs
+use crate::gameset::*;
+
+// Plays hand after hand with the same players, keeping their chips between
+// hands, until one player has all the chips or the hand limit is reached.
+pub struct Session {
+    pub game: Game,
+    pub hand_limit: Option<usize>,
+    pub hands_played: usize,
+    // rake taken by the house over every hand
+    pub house_total: u32,
+}
+
+impl Session {
+    pub fn new(mut players: Vec<Player>, min_bet: u32, hand_limit: Option<usize>) -> Session {
+        players.retain(|p| p.balance > 0);
+        Session {
+            game: Game::new(players, min_bet, true),
+            hand_limit,
+            hands_played: 0,
+            house_total: 0,
+        }
+    }
+
+    pub fn with_config(
+        mut players: Vec<Player>,
+        config: GameConfig,
+        hand_limit: Option<usize>,
+    ) -> Session {
+        players.retain(|p| p.balance > 0);
+        Session {
+            game: Game::with_config(players, config, true),
+            hand_limit,
+            hands_played: 0,
+            house_total: 0,
+        }
+    }
+
+    // Seats and every hand's cards are reproduced by the same seed
+    pub fn with_seed(
+        mut players: Vec<Player>,
+        config: GameConfig,
+        hand_limit: Option<usize>,
+        seed: u64,
+    ) -> Session {
+        players.retain(|p| p.balance > 0);
+        Session {
+            game: Game::with_seed(players, config, true, seed),
+            hand_limit,
+            hands_played: 0,
+            house_total: 0,
+        }
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.game.players
+    }
+
+    // The last player standing, if there is one
+    pub fn winner(&self) -> Option<&Player> {
+        match self.game.players.len() {
+            1 => self.game.players.first(),
+            _ => None,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        let with_chips = self.game.players.iter().filter(|p| p.balance > 0).count();
+        with_chips < 2
+            || self
+                .hand_limit
+                .is_some_and(|limit| self.hands_played >= limit)
+    }
+
+    pub fn play(&mut self) {
+        loop {
+            self.play_hand();
+            if self.finished() {
+                break;
+            }
+            self.next_hand();
+        }
+        self.remove_busted();
+    }
+
+    pub fn play_hand(&mut self) {
+        self.game.play();
+        self.hands_played += 1;
+        self.house_total += self.game.rake;
+    }
+
+    // Removes busted players and deals the next hand
+    pub fn next_hand(&mut self) {
+        self.remove_busted();
+        self.game.new_hand();
+    }
+
+    fn remove_busted(&mut self) {
+        let players = &self.game.players;
+        let button = self.game.button;
+        let button_busted = players[button].balance == 0;
+        let kept_before = players[..button].iter().filter(|p| p.balance > 0).count();
+
+        self.game.players.retain(|p| p.balance > 0);
+        let len = self.game.players.len();
+        if len == 0 {
+            return;
+        }
+        // If the button busted, leave it just before the next seat so it still moves there
+        self.game.button = if button_busted {
+            (kept_before + len - 1) % len
+        } else {
+            kept_before
+        };
+    }
+}
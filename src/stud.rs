@@ -0,0 +1,42 @@
+// stud.rs
+use crate::poker::*;
+use std::collections::HashMap;
+
+// Breaks ties between cards of the same rank: clubs, diamonds, hearts, spades
+pub fn suit_order(suit: Suit) -> u8 {
+    match suit {
+        Suit::Clubs => 0,
+        Suit::Diamonds => 1,
+        Suit::Hearts => 2,
+        Suit::Spades => 3,
+    }
+}
+
+// The player showing the lowest card by this order brings in
+pub fn bring_in_order(card: &Card) -> (Rank, u8) {
+    (card.rank, suit_order(card.suit))
+}
+
+// Strength of the face-up cards, used to pick who acts first on later streets.
+// Only pairs, two pair, trips and quads count, then the ranks from high to low.
+pub fn visible_hand(cards: &[Card]) -> (u8, Vec<Rank>) {
+    let mut counts: HashMap<Rank, usize> = HashMap::new();
+    for card in cards.iter() {
+        *counts.entry(card.rank).or_insert(0) += 1;
+    }
+    let mut groups: Vec<(usize, Rank)> = counts.iter().map(|(r, c)| (*c, *r)).collect();
+    groups.sort_by(|a, b| b.cmp(a));
+
+    let category = match (groups.first(), groups.get(1)) {
+        (Some((4, _)), _) => 4,
+        (Some((3, _)), _) => 3,
+        (Some((2, _)), Some((2, _))) => 2,
+        (Some((2, _)), _) => 1,
+        _ => 0,
+    };
+    let ranks = groups
+        .iter()
+        .flat_map(|(count, rank)| std::iter::repeat_n(*rank, *count))
+        .collect();
+    (category, ranks)
+}
@@ -42,7 +42,7 @@ fn hand_cmp_4() {
     assert_eq!(StraightFlush(King).compare(&RoyalFlush), Less);
 
     let hand4_1 = StraightFlush(Ten);
-    let hand4_2 = Flush(Jack);
+    let hand4_2 = Flush(Jack, Nine, Seven, Three, Two);
     assert_eq!(hand4_1.compare(&hand4_2), Greater);
     assert_eq!(hand4_2.compare(&hand4_1), Less);
 }
@@ -184,7 +184,7 @@ fn hand_calc_6() {
         Card { rank: Three, suit: Spades },
         Card { rank: Nine, suit: Hearts },
     ];
-    assert_eq!(calculate_hand(&hand_6), Flush(Jack));
+    assert_eq!(calculate_hand(&hand_6), Flush(Jack, Nine, Seven, Three, Two));
 }
 
 #[test]
@@ -223,10 +223,10 @@ fn hand_calc_7_3() {
         Card { rank: Four, suit: Clubs },
         Card { rank: Five, suit: Spades },
         Card { rank: Six, suit: Spades },
-        Card { rank: Five, suit: Spades },
-        Card { rank: Five, suit: Spades },
+        Card { rank: Nine, suit: Spades },
+        Card { rank: King, suit: Spades },
     ];
-    assert_eq!(calculate_hand(&hand_7), Flush(Six));
+    assert_eq!(calculate_hand(&hand_7), Flush(King, Nine, Six, Five, Three));
 }
 
 #[test]
@@ -352,3 +352,1950 @@ fn rank_ord_3() {
     assert_eq!(Ace.cmp(&Two), Greater);
 }
 
+
+//
+// SIDE POT TESTS:
+//
+fn all_in_game(balances: &[u32]) -> crate::gameset::Game {
+    use crate::gameset::*;
+    let players = balances
+        .iter()
+        .enumerate()
+        .map(|(i, balance)| {
+            let mut player = Player::new(format!("P{}", i));
+            player.balance = *balance;
+            player
+        })
+        .collect();
+    Game::new(players, 5, false)
+}
+
+#[test]
+fn side_pot_1() {
+    use crate::gameset::Pot;
+    let mut game = all_in_game(&[100, 300, 500]);
+    game.players[0].total_bet = 100;
+    game.players[0].all_in = true;
+    game.players[1].total_bet = 300;
+    game.players[1].all_in = true;
+    game.players[2].total_bet = 300;
+    assert_eq!(
+        game.pots(),
+        vec![
+            Pot { amount: 300, eligible: vec![0, 1, 2] },
+            Pot { amount: 400, eligible: vec![1, 2] },
+        ]
+    );
+}
+
+#[test]
+fn side_pot_2() {
+    use crate::gameset::Pot;
+    // Folded chips stay in the pots, uncalled chips go back to the bettor
+    let mut game = all_in_game(&[50, 200, 500, 500]);
+    game.players[0].total_bet = 50;
+    game.players[0].all_in = true;
+    game.players[1].total_bet = 80;
+    game.players[1].folded = true;
+    game.players[2].total_bet = 400;
+    game.players[3].total_bet = 150;
+    game.players[3].all_in = true;
+    assert_eq!(
+        game.pots(),
+        vec![
+            Pot { amount: 200, eligible: vec![0, 2, 3] },
+            Pot { amount: 230, eligible: vec![2, 3] },
+            Pot { amount: 250, eligible: vec![2] },
+        ]
+    );
+}
+
+#[test]
+fn side_pot_3() {
+    use crate::playerinput::Action;
+    let mut game = all_in_game(&[100, 300, 500]);
+    game.take_action(Action::AllIn).unwrap();
+    game.take_action(Action::AllIn).unwrap();
+    game.take_action(Action::Call).unwrap();
+    assert!(game.ended);
+    // P2 always gets back the 200$ nobody could call
+    assert!(game.players[2].balance >= 200);
+    let total = game.players.iter().map(|p| p.balance).sum::<u32>();
+    assert_eq!(total, 900);
+}
+
+#[test]
+fn side_pot_4() {
+    use crate::playerinput::Action;
+    let mut game = all_in_game(&[100, 300, 500]);
+    game.take_action(Action::Raise(50)).unwrap();
+    game.take_action(Action::AllIn).unwrap();
+    game.take_action(Action::Fold).unwrap();
+    assert!(game.take_action(Action::Raise(400)).is_err());
+    game.take_action(Action::Fold).unwrap();
+    assert!(game.ended);
+    assert_eq!(game.players[0].balance, 50);
+    assert_eq!(game.players[1].balance, 360);
+    assert_eq!(game.players[2].balance, 490);
+}
+
+//
+// BLIND AND BUTTON TESTS:
+//
+#[test]
+fn blinds_1() {
+    let game = all_in_game(&[500, 500, 500, 500]);
+    assert_eq!(game.button, 0);
+    assert_eq!(game.blinds(), (1, 2));
+    assert_eq!(game.players[1].bet, 5);
+    assert_eq!(game.players[2].bet, 10);
+    assert_eq!(game.pot, 15);
+    assert_eq!(game.bet, 10);
+    assert_eq!(game.turn.1, 3);
+}
+
+#[test]
+fn blinds_2() {
+    use crate::playerinput::Action;
+    // Heads-up the button posts the small blind and acts first preflop
+    let mut game = all_in_game(&[500, 500]);
+    assert_eq!(game.blinds(), (0, 1));
+    assert_eq!(game.turn.1, 0);
+    game.take_action(Action::Call).unwrap();
+    // The big blind gets the option
+    assert_eq!(game.turn, (Round::PreFlop, 1));
+    game.take_action(Action::Check).unwrap();
+    assert_eq!(game.turn, (Round::Flop, 1));
+}
+
+#[test]
+fn blinds_3() {
+    use crate::playerinput::Action;
+    let mut game = all_in_game(&[500, 500, 500]);
+    game.take_action(Action::Call).unwrap();
+    game.take_action(Action::Call).unwrap();
+    game.take_action(Action::Check).unwrap();
+    // Postflop action starts left of the button
+    assert_eq!(game.turn, (Round::Flop, 1));
+}
+
+#[test]
+fn button_1() {
+    use crate::playerinput::Action;
+    let mut game = all_in_game(&[500, 0, 500, 500]);
+    // Busted players don't post blinds
+    assert_eq!(game.blinds(), (2, 3));
+    game.take_action(Action::Fold).unwrap();
+    game.take_action(Action::Fold).unwrap();
+    assert!(game.ended);
+    game.new_hand();
+    assert_eq!(game.button, 2);
+    assert_eq!(game.blinds(), (3, 0));
+    game.new_hand();
+    assert_eq!(game.button, 3);
+}
+
+//
+// SESSION TESTS:
+//
+#[test]
+fn session_1() {
+    use crate::gameset::Player;
+    use crate::session::Session;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let mut session = Session::new(players, 5, Some(1));
+    assert!(!session.finished());
+    session.game.take_action(crate::playerinput::Action::Fold).unwrap();
+    session.game.take_action(crate::playerinput::Action::Fold).unwrap();
+    session.hands_played += 1;
+    assert!(session.finished());
+    // Chips won in a hand are kept by the player
+    let big_blind = session.game.blinds().1;
+    assert_eq!(session.players()[big_blind].balance, 505);
+}
+
+#[test]
+fn session_2() {
+    use crate::gameset::Player;
+    use crate::session::Session;
+    let players = ["A", "B", "C", "D"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let mut session = Session::new(players, 5, None);
+    while !session.game.ended {
+        session.game.take_action(crate::playerinput::Action::Fold).unwrap();
+    }
+    let names: Vec<String> = session.players().iter().map(|p| p.name.clone()).collect();
+    // The button busts, the next hand's button is the seat after it
+    let button = session.game.button;
+    session.game.players[button].balance = 0;
+    session.next_hand();
+    assert_eq!(session.players().len(), 3);
+    assert_eq!(session.players()[session.game.button].name, names[(button + 1) % 4]);
+    assert!(session.winner().is_none());
+}
+
+//
+// CONTROLLER TESTS:
+//
+fn scripted_game(scripts: Vec<Vec<crate::playerinput::Action>>) -> crate::gameset::Game {
+    use crate::gameset::*;
+    use crate::playerinput::ScriptedController;
+    let players = scripts
+        .into_iter()
+        .enumerate()
+        .map(|(i, script)| {
+            Player::with_controller(format!("P{}", i), Box::new(ScriptedController::new(script)))
+        })
+        .collect();
+    Game::new(players, 5, false)
+}
+
+#[test]
+fn controller_1() {
+    use crate::playerinput::Action::*;
+    // Button raises, small blind folds, big blind calls and folds to a flop bet
+    let mut game = scripted_game(vec![
+        vec![Raise(30), Raise(50)],
+        vec![Fold],
+        vec![Call, Check, Fold],
+    ]);
+    game.play();
+    assert!(game.ended);
+    assert_eq!(game.players[0].balance, 535);
+    assert_eq!(game.players[1].balance, 495);
+    assert_eq!(game.players[2].balance, 470);
+}
+
+#[test]
+fn controller_2() {
+    use crate::gameset::*;
+    use crate::playerinput::*;
+    // Bots and scripts can share a table
+    let players = vec![
+        Player::with_controller("Bot".into(), Box::new(PassiveBot)),
+        Player::with_controller("Script".into(), Box::new(ScriptedController::new(vec![Action::Fold]))),
+        Player::with_controller("Bot 2".into(), Box::new(PassiveBot)),
+    ];
+    let mut game = Game::new(players, 5, false);
+    let view = game.view(0);
+    assert_eq!(view.to_call(), 10);
+    assert_eq!(view.hole.len(), 2);
+    assert!(view.table.is_empty());
+    game.play();
+    assert!(game.ended);
+    assert_eq!(game.players[1].balance, 495);
+}
+
+#[test]
+#[should_panic]
+fn controller_3() {
+    use crate::playerinput::Action::*;
+    // Scripts fail loudly instead of looping on an illegal action
+    let mut game = scripted_game(vec![vec![Check], vec![], vec![]]);
+    game.play();
+}
+
+//
+// EVENT TESTS:
+//
+#[test]
+fn events_1() {
+    use crate::events::GameEvent;
+    use crate::playerinput::Action::*;
+    let mut game = scripted_game(vec![vec![Fold], vec![Call, Fold], vec![Check]]);
+    game.play();
+    let events = &game.events;
+    assert!(matches!(events[0], GameEvent::HandStarted { button: 0, .. }));
+    assert!(events.contains(&GameEvent::BlindPosted { seat: 2, amount: 10 }));
+    assert!(events.contains(&GameEvent::ActionTaken { seat: 1, action: Call, amount: 5 }));
+    assert!(events.contains(&GameEvent::PotAwarded { pot: 0, seat: 2, amount: 20 }));
+    assert_eq!(events.last(), Some(&GameEvent::HandEnded));
+    let streets = events
+        .iter()
+        .filter(|e| matches!(e, GameEvent::StreetAdvanced { .. }))
+        .count();
+    assert_eq!(streets, 1);
+}
+
+#[test]
+fn events_2() {
+    use crate::events::*;
+    use crate::playerinput::Action::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Counter(Rc<RefCell<usize>>);
+    impl EventSink for Counter {
+        fn on_event(&mut self, _event: &GameEvent) {
+            *self.0.borrow_mut() += 1;
+        }
+    }
+
+    let count = Rc::new(RefCell::new(0));
+    let mut game = scripted_game(vec![vec![Fold], vec![Fold], vec![]]);
+    // Late subscribers still get the events of the current hand
+    game.subscribe(Box::new(Counter(count.clone())));
+    game.play();
+    assert_eq!(*count.borrow(), game.events.len());
+}
+
+//
+// SEED TESTS:
+//
+fn seeded_players() -> Vec<crate::gameset::Player> {
+    use crate::gameset::Player;
+    ["A", "B", "C", "D"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect()
+}
+
+#[test]
+fn seed_1() {
+    use crate::gameset::{Game, GameConfig};
+    let game_1 = Game::with_seed(seeded_players(), GameConfig::new(5), true, 42);
+    let game_2 = Game::with_seed(seeded_players(), GameConfig::new(5), true, 42);
+    let names_1: Vec<&String> = game_1.players.iter().map(|p| &p.name).collect();
+    let names_2: Vec<&String> = game_2.players.iter().map(|p| &p.name).collect();
+    assert_eq!(names_1, names_2);
+    assert_eq!(game_1.table, game_2.table);
+    assert_eq!(game_1.events, game_2.events);
+    assert_eq!(game_1.seed, Some(42));
+}
+
+#[test]
+fn seed_2() {
+    use crate::gameset::{Game, GameConfig};
+    use crate::playerinput::Action;
+    let mut game = Game::with_seed(seeded_players(), GameConfig::new(5), false, 7);
+    while !game.ended {
+        game.take_action(Action::Fold).unwrap();
+    }
+    game.new_hand();
+    // The second hand can be dealt again from its own seed
+    let replay = Game::with_hand_seed(seeded_players(), GameConfig::new(5), game.button, game.hand_seed);
+    assert_eq!(replay.table, game.table);
+    for (player, replayed) in game.players.iter().zip(replay.players.iter()) {
+        assert_eq!(player.hole, replayed.hole);
+    }
+}
+
+#[test]
+fn seed_3() {
+    use crate::gameset::{Game, GameConfig};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    let game_1 = Game::with_rng(seeded_players(), GameConfig::new(5), false, Box::new(StdRng::seed_from_u64(3)));
+    let game_2 = Game::with_rng(seeded_players(), GameConfig::new(5), false, Box::new(StdRng::seed_from_u64(3)));
+    assert_eq!(game_1.hand_seed, game_2.hand_seed);
+    assert_eq!(game_1.table, game_2.table);
+    assert_eq!(game_1.seed, None);
+}
+
+//
+// HAND HISTORY TESTS:
+//
+#[test]
+fn history_1() {
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let mut game = scripted_game(vec![
+        vec![Raise(30), AllIn],
+        vec![Fold],
+        vec![Call, Raise(40), Call],
+    ]);
+    game.play();
+    let history = game.history();
+    assert_eq!(history.blinds, vec![(1, 5), (2, 10)]);
+    assert_eq!(history.actions.len(), 6);
+    assert_eq!(history.actions[4].action, AllIn);
+    assert_eq!(history.actions[4].amount, 470);
+    assert_eq!(history.board.len(), 5);
+    // The text format reads back the same history
+    assert_eq!(HandHistory::parse(&history.to_text()), Ok(history.clone()));
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+}
+
+#[test]
+fn history_2() {
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let mut game = all_in_game(&[500, 500, 500]);
+    for action in [Fold, Call, Check, Check, Raise(20)] {
+        game.take_action(action).unwrap();
+    }
+    let mut history = game.history();
+    // P1 never gets to respond to the raise
+    assert!(replay(&history, Vec::new()).is_err());
+    history.actions.push(HistoryAction {
+        round: Round::Flop,
+        seat: 1,
+        action: Fold,
+        amount: 0,
+    });
+    history = HandHistory::parse(&history.to_text()).unwrap();
+    // The replay doesn't trust the recorded results
+    history.stacks[1] += 10;
+    assert!(replay(&history, Vec::new()).is_err());
+}
+
+#[test]
+fn history_3() {
+    use crate::history::*;
+    assert!(HandHistory::parse("not a history").is_err());
+    let text = "poker_cli hand history v1\nseed 1\nmin_bet 5\nbutton 0\nseat 0 500 Al Pine\nhole 0 Ah Kx\n";
+    assert!(HandHistory::parse(text).is_err());
+    let text = "poker_cli hand history v1\nseed 1\nmin_bet 5\nbutton 0\nseat 0 500 Al Pine\nseat 1 500 Bo\nhole 0 Ah Kd\n";
+    let history = HandHistory::parse(text).unwrap();
+    assert_eq!(history.seats, vec![("Al Pine".to_string(), 500), ("Bo".to_string(), 500)]);
+    assert_eq!("Ah".parse(), Ok(Card { rank: Ace, suit: Hearts }));
+}
+
+#[test]
+fn history_4() {
+    // Broken lines are errors, and so is a history that can't be saved
+    use crate::events::GameEvent;
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let header = "poker_cli hand history v1\nseed 1\nmin_bet 5\nbutton 0\n";
+    assert!(HandHistory::parse(&format!("{}seat 0\n", header)).is_err());
+    assert!(HandHistory::parse(&format!("{}seat 0 500\n", header)).is_err());
+    assert!(HandHistory::parse(&format!("{}seat 0 500 A\nstack 1 500\n", header)).is_err());
+    // Tables the engine can't deal are errors too, not panics
+    let seats = format!("{}seat 0 500 A\nseat 1 500 B\n", header);
+    assert!(HandHistory::parse(&seats).is_ok());
+    assert!(HandHistory::parse(&format!("{}seat 0 500 A\nseat 1 0 B\n", header)).is_err());
+    assert!(HandHistory::parse(&seats.replace("button 0", "button 2")).is_err());
+    assert!(HandHistory::parse(&seats.replace("500 B", "4294967295 B")).is_err());
+    assert!(HandHistory::parse(&format!("{}hole 0 Ah Kd\nhole 1 Ah Qd\n", seats)).is_err());
+    assert!(HandHistory::parse(&format!("{}hole 0 Ah Kd\nboard Kd 2c 3c\n", seats)).is_err());
+    let short_deck = seats.replace("button 0", "variant shortdeck\nbutton 0");
+    assert!(HandHistory::parse(&short_deck).is_ok());
+    assert!(HandHistory::parse(&format!("{}hole 0 Ah 2d\n", short_deck)).is_err());
+    let mut game = all_in_game(&[500, 500]);
+    game.history_dir = Some("/nonexistent/poker_cli".into());
+    game.take_action(Fold).unwrap();
+    assert!(matches!(game.events.last(), Some(GameEvent::HistoryNotSaved { .. })));
+}
+
+//
+// OMAHA TESTS:
+//
+#[test]
+fn omaha_1() {
+    let hole = vec![
+        Card { rank: Ace, suit: Spades },
+        Card { rank: Ace, suit: Diamonds },
+        Card { rank: Queen, suit: Clubs },
+        Card { rank: Queen, suit: Diamonds },
+    ];
+    let board = vec![
+        Card { rank: Two, suit: Hearts },
+        Card { rank: Five, suit: Hearts },
+        Card { rank: Eight, suit: Hearts },
+        Card { rank: Jack, suit: Hearts },
+        Card { rank: King, suit: Hearts },
+    ];
+    // No hearts in the hole, so no flush
+    assert_eq!(calculate_omaha_hand(&hole, &board), Pair(Ace, King, Jack, Eight));
+}
+
+#[test]
+fn omaha_2() {
+    let hole = vec![
+        Card { rank: Ten, suit: Hearts },
+        Card { rank: Three, suit: Spades },
+        Card { rank: Four, suit: Spades },
+        Card { rank: Five, suit: Spades },
+        Card { rank: Six, suit: Clubs },
+    ];
+    let board = vec![
+        Card { rank: Ace, suit: Hearts },
+        Card { rank: King, suit: Hearts },
+        Card { rank: Queen, suit: Hearts },
+        Card { rank: Jack, suit: Hearts },
+        Card { rank: Two, suit: Clubs },
+    ];
+    // Only one hole card fits the royal flush on board
+    assert_eq!(calculate_omaha_hand(&hole, &board), HighCard(Ace, King, Queen, Ten, Six));
+}
+
+#[test]
+fn omaha_3() {
+    use crate::gameset::*;
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    use crate::variant::Variant;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        variant: Variant::Omaha(6),
+        ..GameConfig::new(5)
+    };
+    let mut game = Game::with_seed(players, config, false, 11);
+    assert!(game.players.iter().all(|p| p.hole.len() == 6));
+    for action in [Call, Call, Check, Check, Check, Check, Check, Check, Check] {
+        game.take_action(action).unwrap();
+    }
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    let history = HandHistory::parse(&game.history().to_text()).unwrap();
+    assert_eq!(history.config.variant, Variant::Omaha(6));
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+}
+
+//
+// SHORT DECK TESTS:
+//
+#[test]
+fn short_deck_1() {
+    let cards = vec![
+        Card { rank: Ace, suit: Hearts },
+        Card { rank: Six, suit: Spades },
+        Card { rank: Seven, suit: Clubs },
+        Card { rank: Eight, suit: Diamonds },
+        Card { rank: Nine, suit: Hearts },
+        Card { rank: King, suit: Clubs },
+        Card { rank: King, suit: Hearts },
+    ];
+    assert_eq!(calculate_short_deck_hand(&cards), Straight(Nine));
+    assert_eq!(calculate_hand(&cards), Pair(King, Ace, Nine, Eight));
+}
+
+#[test]
+fn short_deck_2() {
+    use crate::variant::Variant;
+    let flush = Flush(Jack, Nine, Seven, Three, Two);
+    let full_house = FullHouse(Ace, King);
+    assert_eq!(Variant::ShortDeck.compare_hands(&flush, &full_house), Greater);
+    assert_eq!(Variant::ShortDeck.compare_hands(&full_house, &flush), Less);
+    assert_eq!(Variant::Holdem.compare_hands(&flush, &full_house), Less);
+    let ace_flush = Flush(Ace, Nine, Eight, Seven, Six);
+    let king_flush = Flush(King, Queen, Jack, Nine, Eight);
+    assert_eq!(Variant::ShortDeck.compare_hands(&ace_flush, &king_flush), Greater);
+    assert_eq!(Variant::ShortDeck.compare_hands(&StraightFlush(Nine), &flush), Greater);
+}
+
+#[test]
+fn short_deck_3() {
+    use crate::gameset::*;
+    use crate::variant::Variant;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        variant: Variant::ShortDeck,
+        ..GameConfig::new(5)
+    };
+    let game = Game::with_config(players, config, false);
+    // The board is dealt street by street
+    assert!(game.table.is_empty());
+    assert_eq!(game.deck.len() + 3 * 2, 36);
+    let dealt = game.players.iter().flat_map(|p| p.hole.iter());
+    assert!(dealt.chain(game.deck.iter()).all(|c| c.rank >= Six));
+}
+
+#[test]
+fn hand_calc_14() {
+    // A six makes a higher straight than the wheel
+    let hand_14 = vec![
+        Card { rank: Ace, suit: Spades },
+        Card { rank: Two, suit: Diamonds },
+        Card { rank: Three, suit: Clubs },
+        Card { rank: Four, suit: Hearts },
+        Card { rank: Five, suit: Spades },
+        Card { rank: Six, suit: Hearts },
+        Card { rank: King, suit: Spades },
+    ];
+    assert_eq!(calculate_hand(&hand_14), Straight(Six));
+}
+
+#[test]
+fn hand_calc_15() {
+    let hand_15 = vec![
+        Card { rank: King, suit: Spades },
+        Card { rank: Ace, suit: Diamonds },
+        Card { rank: King, suit: Clubs },
+        Card { rank: Ace, suit: Hearts },
+        Card { rank: Ace, suit: Spades },
+        Card { rank: King, suit: Hearts },
+        Card { rank: Two, suit: Spades },
+    ];
+    for _ in 0..20 {
+        assert_eq!(calculate_hand(&hand_15), FullHouse(Ace, King));
+    }
+}
+
+//
+// STUD TESTS:
+//
+fn stud_game(ante: u32) -> crate::gameset::Game {
+    use crate::gameset::*;
+    use crate::variant::Variant;
+    let code = |codes: &[&str]| -> Vec<Card> {
+        codes.iter().map(|c| c.parse::<Card>().unwrap()).collect()
+    };
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        variant: Variant::Stud,
+        forced_bets: crate::betting::ForcedBets { ante, ..Default::default() },
+        ..GameConfig::new(5)
+    };
+    let cards = DealtCards {
+        holes: vec![
+            code(&["3s", "4s", "7h"]),
+            code(&["Ah", "Ad", "5c"]),
+            code(&["Tc", "Th", "6d"]),
+        ],
+        upcards: vec![
+            code(&["2c", "2d", "8c", "Jd"]),
+            code(&["Kh", "Qh", "Ks", "Qs"]),
+            code(&["9d", "As", "3c", "4c"]),
+        ],
+        board: Vec::new(),
+    };
+    Game::with_cards(players, config, 0, 7, cards)
+}
+
+#[test]
+fn stud_1() {
+    // The lowest upcard brings in and the player to their left acts first
+    let game = stud_game(0);
+    assert_eq!(game.turn, (Round::ThirdStreet, 1));
+    assert_eq!(game.players[0].bet, 5);
+    assert_eq!(game.pot, 5);
+    assert!(game.players.iter().all(|p| p.hole.len() == 2 && p.upcards.len() == 1));
+    assert!(game.table.is_empty());
+}
+
+#[test]
+fn stud_2() {
+    // The best visible hand starts each later street
+    use crate::playerinput::Action::*;
+    let mut game = stud_game(0);
+    game.take_action(Call).unwrap();
+    game.take_action(Call).unwrap();
+    // pair of twos showing
+    assert_eq!(game.turn, (Round::FourthStreet, 0));
+    for _ in 0..3 {
+        game.take_action(Check).unwrap();
+    }
+    // pair of kings beats the twos
+    assert_eq!(game.turn, (Round::FifthStreet, 1));
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    assert!(game.players.iter().all(|p| p.cards().len() == 7));
+    assert_eq!(game.history().awards, vec![(0, 1, 15)]);
+}
+
+#[test]
+fn stud_3() {
+    // Antes go in the pot but don't count as a bet
+    let game = stud_game(1);
+    assert_eq!(game.pot, 3 + 5);
+    assert_eq!(game.players[1].bet, 0);
+    assert_eq!(game.players[1].total_bet, 1);
+}
+
+#[test]
+fn stud_4() {
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let mut game = stud_game(1);
+    for action in [Raise(20), Fold, Call] {
+        game.take_action(action).unwrap();
+    }
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    let history = game.history();
+    assert_eq!(history.bring_in, Some((0, 5)));
+    assert_eq!(HandHistory::parse(&history.to_text()).unwrap(), history);
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+}
+
+#[test]
+fn stud_5() {
+    use crate::stud::*;
+    let code = |codes: &[&str]| -> Vec<Card> {
+        codes.iter().map(|c| c.parse::<Card>().unwrap()).collect()
+    };
+    assert!(visible_hand(&code(&["2c", "2d"])) > visible_hand(&code(&["Ah", "Kh"])));
+    assert!(visible_hand(&code(&["Ah", "Kh", "3c"])) > visible_hand(&code(&["Ah", "Qh", "Jc"])));
+    assert!(visible_hand(&code(&["3h", "3c", "2d", "2s"])) > visible_hand(&code(&["Ah", "Ac", "Kd", "Qs"])));
+    assert!(bring_in_order(&code(&["2c"])[0]) < bring_in_order(&code(&["2s"])[0]));
+}
+
+//
+// EVALUATOR TESTS:
+//
+#[test]
+fn evaluator_1() {
+    // Same results as the HashMap based evaluator on random hands
+    use crate::bench::*;
+    use crate::hands::WHEEL;
+    for size in [5, 7] {
+        for cards in random_hands(20_000, size, size as u64) {
+            assert_eq!(calculate_hand(&cards), hash_map_hand(&cards, &WHEEL), "{:?}", cards);
+        }
+    }
+}
+
+#[test]
+fn evaluator_2() {
+    use crate::bench::*;
+    use crate::hands::SHORT_DECK_WHEEL;
+    let hands = random_hands(20_000, 7, 99)
+        .into_iter()
+        .filter(|cards| cards.iter().all(|c| c.rank >= Six));
+    for cards in hands {
+        assert_eq!(
+            calculate_short_deck_hand(&cards),
+            hash_map_hand(&cards, &SHORT_DECK_WHEEL)
+        );
+    }
+}
+
+#[test]
+fn evaluator_3() {
+    // Two trios and three pairs
+    let cards: Vec<Card> = ["9s", "9h", "9d", "4c", "4s", "4h", "Kd"]
+        .iter()
+        .map(|c| c.parse::<Card>().unwrap())
+        .collect();
+    assert_eq!(calculate_hand(&cards), FullHouse(Nine, Four));
+    let cards: Vec<Card> = ["9s", "9h", "3d", "3c", "4s", "4h", "2d"]
+        .iter()
+        .map(|c| c.parse::<Card>().unwrap())
+        .collect();
+    assert_eq!(calculate_hand(&cards), TwoPair(Nine, Four, Three));
+}
+
+//
+// BEST HAND TESTS:
+//
+fn cards(codes: &str) -> Vec<Card> {
+    codes.split(' ').map(|c| c.parse::<Card>().unwrap()).collect()
+}
+
+#[test]
+fn best_hand_1() {
+    // Flushes with the same top card are decided by the other four
+    let flush_1 = calculate_hand(&cards("Ah Qh 9h 7h 3h Kc 2d"));
+    let flush_2 = calculate_hand(&cards("Ah Qh 9h 6h 5h Kc 2d"));
+    assert_eq!(flush_1, Flush(Ace, Queen, Nine, Seven, Three));
+    assert_eq!(flush_1.compare(&flush_2), Greater);
+    assert_eq!(flush_2.compare(&flush_1), Less);
+}
+
+#[test]
+fn best_hand_2() {
+    let best = calculate_best_hand(&cards("Ah Qh 9h 7h 3h 2h Kc"));
+    assert_eq!(best.cards.to_vec(), cards("Ah Qh 9h 7h 3h"));
+    let best = calculate_best_hand(&cards("9s 9h 4d 4c Kd 2s 3h"));
+    assert_eq!(best.hand, TwoPair(Nine, Four, King));
+    assert_eq!(best.cards.to_vec(), cards("9s 9h 4d 4c Kd"));
+}
+
+#[test]
+fn best_hand_3() {
+    // Straights, including the wheel and a straight flush next to a plain straight
+    let best = calculate_best_hand(&cards("Ah 2c 3d 4s 5h Kc Kd"));
+    assert_eq!(best.cards.to_vec(), cards("5h 4s 3d 2c Ah"));
+    let best = calculate_best_hand(&cards("9d 8d 7d 6d 5d Tc 4d"));
+    assert_eq!(best.hand, StraightFlush(Nine));
+    assert_eq!(best.cards.to_vec(), cards("9d 8d 7d 6d 5d"));
+    let best = calculate_short_deck_best_hand(&cards("Ah 6c 7d 8s 9h Kc Kd"));
+    assert_eq!(best.cards.to_vec(), cards("9h 8s 7d 6c Ah"));
+}
+
+#[test]
+fn best_hand_4() {
+    let best = calculate_omaha_best_hand(&cards("Ah Kh 2c 2d"), &cards("Qh 7h 3h Js 2s"));
+    assert_eq!(best.hand, Flush(Ace, King, Queen, Seven, Three));
+    assert_eq!(best.cards.to_vec(), cards("Ah Kh Qh 7h 3h"));
+}
+
+//
+// EQUITY TESTS:
+//
+#[test]
+fn equity_1() {
+    // The board plays for both
+    use crate::equity::*;
+    let mut query = EquityQuery::new(vec![cards("2c 3d"), cards("2h 3s")]);
+    query.board = cards("Ah Kh Qh Jh Th");
+    let equity = query.calculate().unwrap();
+    assert!(equity.exhaustive);
+    assert_eq!(equity.runouts, 1);
+    assert_eq!(equity.players[0].tie, 100.0);
+    assert_eq!(equity.players[1].equity, 50.0);
+}
+
+#[test]
+fn equity_2() {
+    // One card to come: 9 hearts, 3 Aces and 3 Kings beat the Queens
+    use crate::equity::*;
+    let mut query = EquityQuery::new(vec![cards("Ah Kh"), cards("Qs Qd")]);
+    query.board = cards("2h 7h 9c 3s");
+    let equity = query.calculate().unwrap();
+    assert_eq!(equity.runouts, 44);
+    assert_eq!(equity.players[0].win, 100.0 * 15.0 / 44.0);
+    assert_eq!(equity.players[1].win, 100.0 * 29.0 / 44.0);
+}
+
+#[test]
+fn equity_3() {
+    use crate::equity::*;
+    let mut query = EquityQuery::new(vec![cards("As Ad"), cards("Ks Kd")]);
+    query.samples = 20_000;
+    query.seed = 5;
+    let equity = query.calculate().unwrap();
+    assert!(!equity.exhaustive);
+    assert!((80.0..=85.0).contains(&equity.players[0].equity));
+    let total: f64 = equity.players.iter().map(|p| p.equity).sum();
+    assert!((total - 100.0).abs() < 1e-9);
+    // same seed, same result
+    assert_eq!(query.calculate().unwrap(), equity);
+}
+
+#[test]
+fn equity_4() {
+    use crate::equity::*;
+    let query = EquityQuery::new(vec![cards("As Ad"), cards("As Kd")]);
+    assert_eq!(query.calculate(), Err("As is used twice".to_string()));
+    let query = EquityQuery::new(vec![cards("As Ad")]);
+    assert!(query.calculate().is_err());
+    let mut query = EquityQuery::new(vec![cards("As Ad"), cards("Ks Kd")]);
+    query.dead = cards("Ah");
+    query.board = cards("2c 2d 7h 8h");
+    let equity = query.calculate().unwrap();
+    // the Kings need one of the 2 Kings left in 43 cards
+    assert_eq!(equity.runouts, 43);
+    assert_eq!(equity.players[1].win, 100.0 * 2.0 / 43.0);
+}
+
+//
+// RANGE TESTS:
+//
+#[test]
+fn range_1() {
+    use crate::range::*;
+    assert_eq!(Range::parse("AKs").unwrap().combos.len(), 4);
+    assert_eq!(Range::parse("AKo").unwrap().combos.len(), 12);
+    assert_eq!(Range::parse("AK").unwrap().combos.len(), 16);
+    assert_eq!(Range::parse("TT").unwrap().combos.len(), 6);
+    assert_eq!(Range::parse("AhKh").unwrap().combos.len(), 1);
+    // TT, JJ, QQ, KK, AA
+    assert_eq!(Range::parse("TT+").unwrap().combos.len(), 30);
+    // A2s, A3s, A4s, A5s
+    assert_eq!(Range::parse("A2s-A5s").unwrap().combos.len(), 16);
+    // 76s, 65s, 54s
+    assert_eq!(Range::parse("76s-54s").unwrap().combos.len(), 12);
+    // ATs, AJs, AQs, AKs
+    assert_eq!(Range::parse("ATs+").unwrap().combos.len(), 16);
+    let range = Range::parse("AKs, TT+, A2s-A5s, 76s-54s, KQo").unwrap();
+    assert_eq!(range.combos.len(), 4 + 30 + 16 + 12 + 12);
+}
+
+#[test]
+fn range_2() {
+    use crate::range::*;
+    // Repeated combos keep the last weight
+    let range = Range::parse("AK, AKs:0.5").unwrap();
+    assert_eq!(range.combos.len(), 16);
+    let half: Vec<&Combo> = range.combos.iter().filter(|c| c.weight == 0.5).collect();
+    assert_eq!(half.len(), 4);
+    assert!(half.iter().all(|c| c.cards[0].suit == c.cards[1].suit));
+    for text in ["AKx", "AAs", "TT-AKs", "AK:2", "", "AhAh"] {
+        assert!(Range::parse(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn range_3() {
+    // Card removal: an Ace on the board leaves 3 AA combos
+    use crate::range::*;
+    let range = Range::parse("AA, KK").unwrap();
+    assert_eq!(range.without(&cards("As 7d 2c")).len(), 3 + 6);
+}
+
+#[test]
+fn range_4() {
+    // A range of a single hand gives the same result as the hand itself
+    use crate::equity::*;
+    use crate::range::*;
+    let board = cards("2h 7h 9c");
+    let mut query = EquityQuery::new(vec![cards("Ah Kh"), cards("Qs Qd")]);
+    query.board = board.clone();
+    let mut range_query = RangeEquityQuery::new(vec![
+        Range::parse("AhKh").unwrap(),
+        Range::parse("QsQd").unwrap(),
+    ]);
+    range_query.board = board;
+    assert_eq!(range_query.calculate(), query.calculate());
+}
+
+#[test]
+fn range_5() {
+    // AA against KK, on a King high board the Kings that are left always win
+    use crate::equity::*;
+    use crate::range::*;
+    let mut query = RangeEquityQuery::new(vec![
+        Range::from_hand(["As".parse::<Card>().unwrap(), "Ad".parse::<Card>().unwrap()]),
+        Range::parse("KK").unwrap(),
+    ]);
+    query.board = cards("2c 7d 9h Ks");
+    let equity = query.calculate().unwrap();
+    assert!(equity.exhaustive);
+    assert_eq!(equity.runouts, 3 * 44);
+    assert!((equity.players[1].equity - 100.0 * 42.0 / 44.0).abs() < 1e-9);
+
+    query.board = Vec::new();
+    query.samples = 5_000;
+    let equity = query.calculate().unwrap();
+    assert!(!equity.exhaustive);
+    assert!((78.0..=86.0).contains(&equity.players[0].equity));
+}
+
+//
+// OUTS TESTS:
+//
+fn outs_by_draw(outs: &[crate::outs::Out], draw: crate::outs::Draw) -> usize {
+    outs.iter().filter(|o| o.draw == draw).count()
+}
+
+#[test]
+fn outs_1() {
+    // Nut flush draw with two overcards on the flop
+    use crate::outs::*;
+    use crate::variant::Variant;
+    let outs = find_outs(Variant::Holdem, &cards("Ah Kh"), &cards("2h 7h 9c"), &[]);
+    assert_eq!(outs_by_draw(&outs, Draw::Flush), 9);
+    assert_eq!(outs_by_draw(&outs, Draw::Pair), 6);
+    assert!(outs.iter().all(|o| o.draw != Draw::OpenEnded && o.draw != Draw::Gutshot));
+}
+
+#[test]
+fn outs_2() {
+    use crate::outs::*;
+    use crate::variant::Variant;
+    // 8-9 on 6-7-K: any Five or Ten makes a straight
+    let outs = find_outs(Variant::Holdem, &cards("8s 9d"), &cards("6c 7h Kd 2s"), &[]);
+    assert_eq!(outs_by_draw(&outs, Draw::OpenEnded), 8);
+    assert!(outs.iter().all(|o| o.draw != Draw::Backdoor));
+    // 8-9 on 5-6-K-2: only a Seven fills the gap
+    let outs = find_outs(Variant::Holdem, &cards("8s 9d"), &cards("5c 6h Kd 2s"), &[]);
+    assert_eq!(outs_by_draw(&outs, Draw::Gutshot), 4);
+    assert!(outs.iter().filter(|o| o.draw == Draw::Gutshot).all(|o| o.card.rank == Seven));
+}
+
+#[test]
+fn outs_3() {
+    use crate::outs::*;
+    use crate::variant::Variant;
+    // Pocket pair: 2 set outs, and pairing the board doesn't count
+    let outs = find_outs(Variant::Holdem, &cards("5s 5d"), &cards("Kc 9h 2d 3s"), &[]);
+    assert_eq!(outs_by_draw(&outs, Draw::Set), 2);
+    assert!(outs.iter().all(|o| o.card.rank != King && o.card.rank != Nine));
+}
+
+#[test]
+fn outs_4() {
+    use crate::outs::*;
+    use crate::variant::Variant;
+    // Three hearts on the flop: the other hearts are backdoor flush cards
+    let outs = find_outs(Variant::Holdem, &cards("Ah 3h"), &cards("Kh 9c 8d"), &[]);
+    assert_eq!(outs_by_draw(&outs, Draw::Backdoor), 10);
+    assert_eq!(outs_by_draw(&outs, Draw::Flush), 0);
+    // No backdoor cards on the turn
+    let outs = find_outs(Variant::Holdem, &cards("Ah 3h"), &cards("Kh 9c 8d 2s"), &[]);
+    assert_eq!(outs_by_draw(&outs, Draw::Backdoor), 0);
+}
+
+#[test]
+fn outs_5() {
+    use crate::outs::*;
+    use crate::variant::Variant;
+    // Against a known set, only the flush cards that don't pair the board win
+    let outs = find_outs(
+        Variant::Holdem,
+        &cards("Ah Kh"),
+        &cards("2h 7h 9c 3s"),
+        &[cards("9s 9d")],
+    );
+    assert_eq!(outs.len(), 9 - 2);
+    assert!(outs.iter().all(|o| o.draw == Draw::Flush));
+}
+
+//
+// LOW HAND TESTS:
+//
+#[test]
+fn low_1() {
+    // A-5: the wheel is the best low, pairs count against you
+    let wheel = calculate_ace_to_five_low(&cards("Ah 2c 3d 4s 5h Kc Kd"), None).unwrap();
+    let six_four = calculate_ace_to_five_low(&cards("Ah 2c 3d 4s 6h Kc Kd"), None).unwrap();
+    let pair = calculate_ace_to_five_low(&cards("Ah Ac 3d 3s 6h 6c 4d"), None).unwrap();
+    assert_eq!(wheel, LowHand::AceToFive(0, [Five, Four, Three, Two, Ace]));
+    assert_eq!(wheel.compare(&six_four), Greater);
+    assert_eq!(pair.compare(&six_four), Less);
+    assert_eq!(pair, LowHand::AceToFive(1, [Ace, Ace, Six, Four, Three]));
+}
+
+#[test]
+fn low_2() {
+    // 8-or-better
+    let eight = cards("8h 2c 3d 4s 6h Kc Kd");
+    let nine = cards("9h 2c 3d 4s 6h Kc Kd");
+    assert!(calculate_ace_to_five_low(&eight, Some(Eight)).is_some());
+    assert_eq!(calculate_ace_to_five_low(&nine, Some(Eight)), None);
+    assert!(calculate_ace_to_five_low(&nine, None).is_some());
+}
+
+#[test]
+fn low_3() {
+    // 2-7: 7-5-4-3-2 is the best, A-2-3-4-5 is Ace high and straights count
+    let seven_five = calculate_deuce_to_seven_low(&cards("7h 5c 4d 3s 2h"));
+    let wheel = calculate_deuce_to_seven_low(&cards("Ah 5c 4d 3s 2h"));
+    let straight = calculate_deuce_to_seven_low(&cards("6h 5c 4d 3s 2h"));
+    let king = calculate_deuce_to_seven_low(&cards("Kh 5c 4d 3s 2h"));
+    assert_eq!(wheel, LowHand::DeuceToSeven(HighCard(Ace, Five, Four, Three, Two)));
+    assert_eq!(seven_five.compare(&straight), Greater);
+    assert_eq!(king.compare(&wheel), Greater);
+    assert_eq!(straight.compare(&wheel), Less);
+}
+
+#[test]
+fn low_4() {
+    // Omaha lows use exactly two hole cards
+    let low = calculate_omaha_low(&cards("Ah 2c Kd Ks"), &cards("3c 4d 5s Kh Qc"), Some(Eight));
+    assert_eq!(low, Some(LowHand::AceToFive(0, [Five, Four, Three, Two, Ace])));
+    let low = calculate_omaha_low(&cards("Ah 2c 3h 4s"), &cards("5c 6d Ks Kh Qc"), Some(Eight));
+    assert_eq!(low, None);
+    // Lows read from high to low
+    assert_eq!(LowHand::AceToFive(0, [Five, Four, Three, Two, Ace]).to_string(), "5-4-3-2-A low");
+    let low = calculate_deuce_to_seven_low(&cards("7h 5d 4c 3s 2h"));
+    assert_eq!(low.to_string(), "2-7 low, High card, Seven with Five, Four, Three and Two");
+}
+
+fn hi_lo_game(holes: Vec<Vec<Card>>, board: Vec<Card>) -> crate::gameset::Game {
+    use crate::gameset::*;
+    use crate::variant::Variant;
+    let players = ["A", "B", "C"][..holes.len()]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        variant: Variant::OmahaHiLo(4),
+        ..GameConfig::new(5)
+    };
+    let dealt = DealtCards {
+        holes,
+        upcards: Vec::new(),
+        board,
+    };
+    Game::with_cards(players, config, 0, 1, dealt)
+}
+
+#[test]
+fn low_5() {
+    // Kings take the high half, A-2 the low half
+    use crate::playerinput::Action::*;
+    let mut game = hi_lo_game(
+        vec![cards("Ks Kd Qs Qd"), cards("Ah 2h 9c 9d")],
+        cards("3c 4d 7s Kh Jc"),
+    );
+    game.take_action(Call).unwrap();
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    assert_eq!(game.history().awards, vec![(0, 0, 10), (0, 1, 10)]);
+}
+
+#[test]
+fn low_6() {
+    // Without a qualifying low the high hand scoops
+    use crate::playerinput::Action::*;
+    let mut game = hi_lo_game(
+        vec![cards("Ks Kd Qs Qd"), cards("Ah 2h 9c 9d")],
+        cards("3c Td 7s Kh Jc"),
+    );
+    game.take_action(Call).unwrap();
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    assert_eq!(game.history().awards, vec![(0, 0, 20)]);
+}
+
+//
+// CARD NOTATION TESTS:
+//
+#[test]
+fn notation_1() {
+    // Short and long forms
+    let card = Card { rank: Ten, suit: Clubs };
+    assert_eq!(card.to_string(), "Tc");
+    assert_eq!(format!("{:#}", card), "Ten of Clubs");
+    assert_eq!(format!("{:#}", Queen), "Queen");
+    assert_eq!(Spades.to_string(), "s");
+    assert_eq!(format!("{:+}", card), "T♣");
+    assert_eq!(format!("{:+}", Hearts), "♥");
+    assert_eq!(format!("{:+}", Cards(cards("Ah Kd"))), "A♥ K♦");
+}
+
+#[test]
+fn notation_2() {
+    // Every way to write the Ace of Hearts
+    let ace = Card { rank: Ace, suit: Hearts };
+    for text in ["Ah", "aH", "A♥", "A♡", "Ace of Hearts", "ace of heart", "🂱"] {
+        assert_eq!(text.parse(), Ok(ace), "{}", text);
+    }
+    assert_eq!("10d".parse(), Ok(Card { rank: Ten, suit: Diamonds }));
+}
+
+#[test]
+fn notation_3() {
+    // Every card goes back and forth through each notation
+    for suit in SUITS {
+        for rank in RANKS {
+            let card = Card { rank, suit };
+            assert_eq!(card.to_string().parse(), Ok(card));
+            assert_eq!(format!("{:#}", card).parse(), Ok(card));
+            assert_eq!(format!("{:+}", card).parse(), Ok(card));
+            assert_eq!(Card::from_playing_card(card.playing_card()), Some(card));
+        }
+    }
+    assert_eq!(Card { rank: King, suit: Spades }.playing_card(), '🂮');
+    // the Knight isn't a card here
+    assert_eq!(Card::from_playing_card('🂬'), None);
+}
+
+#[test]
+fn notation_4() {
+    // Lists
+    let hand = Cards(vec![
+        Card { rank: Ace, suit: Hearts },
+        Card { rank: Ten, suit: Diamonds },
+        Card { rank: Two, suit: Clubs },
+    ]);
+    for text in ["Ah Td 2c", "AhTd2c", "Ah,Td, 2c", "A♥10♦2♣", "🂱🃊🃒"] {
+        assert_eq!(text.parse(), Ok(hand.clone()), "{}", text);
+    }
+    assert_eq!(hand.to_string(), "Ah Td 2c");
+    let long = format!("{:#}", hand);
+    assert_eq!(long, "Ace of Hearts, Ten of Diamonds, Two of Clubs");
+    assert_eq!(long.parse(), Ok(hand));
+    assert_eq!("".parse(), Ok(Cards(Vec::new())));
+}
+
+#[test]
+fn notation_5() {
+    // Errors say which card and why
+    assert_eq!(
+        "Ax".parse::<Card>(),
+        Err("Invalid card 'Ax': unknown suit 'x'".to_string())
+    );
+    assert_eq!(
+        "1h".parse::<Card>(),
+        Err("Invalid card '1h': unknown rank '1'".to_string())
+    );
+    assert_eq!(
+        "Prince of Hearts".parse::<Card>(),
+        Err("Invalid card 'Prince of Hearts': unknown rank 'Prince'".to_string())
+    );
+    assert_eq!(
+        "Ah Kx".parse::<Cards>(),
+        Err("Invalid card 'Kx': unknown suit 'x'".to_string())
+    );
+    assert!("A".parse::<Card>().is_err());
+    assert!("Ah Kd".parse::<Card>().is_err());
+    // Non-ASCII text is an error, not a panic
+    assert!("ȺȺȺȺȺ of h".parse::<Card>().is_err());
+    assert!("ȺȺȺȺȺ of h".parse::<Cards>().is_err());
+}
+
+//
+// HAND DESCRIPTION TESTS:
+//
+#[test]
+fn describe_1() {
+    assert_eq!(FullHouse(King, Ten).to_string(), "Full house, Kings full of Tens");
+    assert_eq!(
+        TwoPair(Jack, Four, Ace).to_string(),
+        "Two pair, Jacks and Fours with an Ace kicker"
+    );
+    assert_eq!(
+        Pair(Six, Ace, Nine, Four).to_string(),
+        "Pair of Sixes with Ace, Nine and Four kickers"
+    );
+    assert_eq!(
+        HighCard(Ace, King, Nine, Seven, Four).to_string(),
+        "High card, Ace with King, Nine, Seven and Four"
+    );
+    assert_eq!(Straight(Five).to_string(), "Straight, Five high");
+    assert_eq!(
+        FourOfAKind(Nine, Eight).to_string(),
+        "Four of a kind, Nines with an Eight kicker"
+    );
+    assert_eq!(RoyalFlush.to_string(), "Royal flush");
+}
+
+#[test]
+fn describe_2() {
+    // Different categories
+    assert_eq!(
+        Flush(King, Nine, Six, Five, Three).explain_win(&Straight(Ace)),
+        "wins with a flush over a straight"
+    );
+    assert_eq!(
+        TwoPair(Three, Two, Four).explain_win(&Pair(Ace, King, Queen, Jack)),
+        "wins with two pair over a pair"
+    );
+}
+
+#[test]
+fn describe_3() {
+    // Same category, decided by the first different rank in compare order
+    assert_eq!(
+        Pair(Ace, King, Nine, Four).explain_win(&Pair(Ace, King, Eight, Seven)),
+        "wins on the second kicker, Nine over Eight"
+    );
+    assert_eq!(
+        FullHouse(King, Ten).explain_win(&FullHouse(King, Nine)),
+        "wins on the pair, Tens over Nines"
+    );
+    assert_eq!(
+        TwoPair(Jack, Four, Ace).explain_win(&TwoPair(Jack, Four, King)),
+        "wins on the kicker, Ace over King"
+    );
+    assert_eq!(
+        Flush(Ace, Jack, Nine, Six, Three).explain_win(&Flush(Ace, Jack, Nine, Six, Two)),
+        "wins on the fifth card, Three over Two"
+    );
+    assert_eq!(
+        Straight(Six).explain_win(&Straight(Six)),
+        "ties with the same hand"
+    );
+}
+
+//
+// WILD CARD TESTS:
+//
+#[test]
+fn wild_1() {
+    // Five of a kind beats everything
+    assert_eq!(calculate_wild_hand(&cards("As Ad Ac 2h 2s"), &[Two]), FiveOfAKind(Ace));
+    assert_eq!(FiveOfAKind(Two).compare(&RoyalFlush), Greater);
+    assert_eq!(FiveOfAKind(Two).category(), 10);
+    assert_eq!(FiveOfAKind(Ace).to_string(), "Five of a kind, Aces");
+    // Without the wild rank the deuces are just a pair
+    assert_eq!(calculate_wild_hand(&cards("As Ad Ac 2h 2s"), &[]), FullHouse(Ace, Two));
+}
+
+#[test]
+fn wild_2() {
+    // A joker fills the best hand it can
+    assert_eq!(calculate_wild_hand(&cards("Xs Kh Qh Jh Th 2c 3d"), &[]), RoyalFlush);
+    assert_eq!(
+        calculate_wild_hand(&cards("Xh 9s 7s 4s 2s Kd Qc"), &[]),
+        Flush(Ace, Nine, Seven, Four, Two)
+    );
+    assert_eq!(calculate_wild_hand(&cards("Xs 9h 8d 6c 5s 2h 2d"), &[]), Straight(Nine));
+    assert_eq!(calculate_wild_hand(&cards("Xs Kh Kd 7c 7s 3h 2d"), &[]), FullHouse(King, Seven));
+    assert_eq!(
+        calculate_wild_hand(&cards("Xs Kh Kd Kc 7s 3h 2d"), &[]),
+        FourOfAKind(King, Seven)
+    );
+    assert_eq!(
+        calculate_wild_hand(&cards("Xs Kh 9d 7c 4s 3h Jd"), &[]),
+        Pair(King, Jack, Nine, Seven)
+    );
+}
+
+#[test]
+fn wild_3() {
+    // Two wild cards: a joker and a deuce
+    assert_eq!(calculate_wild_hand(&cards("Xs 2d Ah 5c 4d"), &[Two]), Straight(Five));
+    assert_eq!(calculate_wild_hand(&cards("Xs 2d 8h 5c 4d"), &[Two]), Straight(Eight));
+    assert_eq!(calculate_wild_hand(&cards("Xs Xh 2d 2c 2s"), &[Two]), FiveOfAKind(Ace));
+    // In short deck the joker can't be a Five
+    let short_deck = crate::variant::Variant::ShortDeck;
+    let best = short_deck.wild_best_hand(&cards("Xs 6h"), &cards("7d 8c Ac"), &[]);
+    assert_eq!(best.hand, Straight(Nine));
+}
+
+#[test]
+fn wild_4() {
+    // Jokers in the deck, and in the notation
+    use crate::gameset::*;
+    let players = vec![Player::new("A".to_string()), Player::new("B".to_string())];
+    let config = GameConfig {
+        jokers: 2,
+        ..GameConfig::new(5)
+    };
+    let dealt = DealtCards {
+        holes: vec![cards("Xs Ah")],
+        ..DealtCards::default()
+    };
+    let game = Game::with_cards(players, config, 0, 1, dealt);
+    let mut all: Vec<Card> = game.deck.clone();
+    all.extend(game.players.iter().flat_map(|p| p.hole.clone()));
+    all.extend(game.table.clone());
+    assert_eq!(all.len(), 54);
+    assert!(JOKERS.iter().all(|joker| all.contains(joker)));
+    assert_eq!("Xh".parse(), Ok(JOKERS[1]));
+    assert_eq!(JOKERS[0].playing_card(), '🃏');
+}
+
+#[test]
+fn wild_5() {
+    // Deuces wild: a straight made with a deuce beats trip Aces
+    use crate::gameset::*;
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let players = vec![Player::new("A".to_string()), Player::new("B".to_string())];
+    let config = GameConfig {
+        wild: vec![Two],
+        ..GameConfig::new(5)
+    };
+    let dealt = DealtCards {
+        holes: vec![cards("Ah Ad"), cards("2c 7d")],
+        upcards: Vec::new(),
+        board: cards("As Th 9h Jc 4d"),
+    };
+    let mut game = Game::with_cards(players, config, 0, 1, dealt);
+    game.take_action(Call).unwrap();
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    assert_eq!(game.players[1].get_hand(&game).hand, Straight(Jack));
+    let history = game.history();
+    assert_eq!(history.awards, vec![(0, 1, 20)]);
+    assert_eq!(HandHistory::parse(&history.to_text()), Ok(history.clone()));
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+}
+
+#[test]
+#[should_panic(expected = "No wild cards in hi-lo games")]
+fn wild_6() {
+    // Hi-lo lows can't be made with jokers, the engine refuses the game
+    use crate::gameset::*;
+    use crate::variant::Variant;
+    let players = vec![Player::new("A".to_string()), Player::new("B".to_string())];
+    let config = GameConfig {
+        variant: Variant::StudHiLo,
+        jokers: 1,
+        ..GameConfig::new(5)
+    };
+    assert!(config.validate().is_err());
+    Game::with_config(players, config, false);
+}
+
+//
+// BETTING STRUCTURE TESTS:
+//
+fn betting_game(betting: crate::betting::Betting) -> crate::gameset::Game {
+    use crate::gameset::*;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        betting,
+        ..GameConfig::new(5)
+    };
+    Game::with_cards(players, config, 0, 1, DealtCards::default())
+}
+
+#[test]
+fn betting_1() {
+    // No-limit: a raise must be at least as big as the last one
+    use crate::betting::Betting;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::NoLimit);
+    assert_eq!(game.raise_range(0), Some((20, 500)));
+    assert!(game.take_action(Raise(15)).is_err());
+    game.take_action(Raise(30)).unwrap();
+    assert_eq!(game.raise_range(1), Some((50, 500)));
+    assert!(game.take_action(Raise(40)).is_err());
+    game.take_action(Raise(50)).unwrap();
+    assert_eq!(game.raise_range(2), Some((70, 500)));
+}
+
+#[test]
+fn betting_2() {
+    // Pot-limit: the call counts as part of the pot
+    use crate::betting::Betting;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::PotLimit);
+    // 10 to call, then the 15 in the blinds and the call
+    assert_eq!(game.raise_range(0), Some((20, 35)));
+    assert!(game.take_action(Raise(40)).is_err());
+    assert!(game.take_action(AllIn).is_err());
+    game.take_action(Raise(35)).unwrap();
+    // 30 to call, the pot is 50 and the call
+    assert_eq!(game.raise_range(1), Some((60, 115)));
+}
+
+#[test]
+fn betting_3() {
+    // Fixed-limit: one bet per raise, four bets per street, bigger bets on the turn
+    use crate::betting::Betting;
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::Limit);
+    assert_eq!(game.raise_range(0), Some((20, 20)));
+    assert!(game.take_action(Raise(25)).is_err());
+    game.take_action(Raise(20)).unwrap();
+    game.take_action(Raise(30)).unwrap();
+    game.take_action(Raise(40)).unwrap();
+    assert_eq!(game.raise_range(0), None);
+    assert!(game.take_action(Raise(50)).is_err());
+    game.take_action(Call).unwrap();
+    game.take_action(Call).unwrap();
+    assert_eq!(game.turn, (Round::Flop, 1));
+    assert_eq!(game.raise_range(1), Some((10, 10)));
+    for _ in 0..3 {
+        game.take_action(Check).unwrap();
+    }
+    assert_eq!(game.raise_range(1), Some((20, 20)));
+    let history = game.history();
+    assert_eq!(HandHistory::parse(&history.to_text()), Ok(history.clone()));
+}
+
+#[test]
+fn betting_4() {
+    // Short stacks can only raise all-in
+    use crate::betting::Betting;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::PotLimit);
+    game.players[0].balance = 15;
+    assert_eq!(game.raise_range(0), Some((15, 15)));
+    game.take_action(AllIn).unwrap();
+    assert_eq!(game.bet, 15);
+    game.players[1].balance = 5;
+    // the small blind can only call all-in
+    assert_eq!(game.raise_range(1), None);
+}
+
+//
+// RAISE TESTS:
+//
+#[test]
+fn raise_1() {
+    // An all-in for less than a full raise doesn't reopen the betting
+    use crate::betting::Betting;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::NoLimit);
+    game.players[1].balance = 35;
+    game.take_action(Raise(30)).unwrap();
+    // 10 more than the raise to 30, which was a raise of 20
+    game.take_action(AllIn).unwrap();
+    assert_eq!(game.bet, 40);
+    assert_eq!(game.raise_range(2), Some((60, 500)));
+    game.take_action(Call).unwrap();
+    assert_eq!(game.raise_range(0), None);
+    assert!(game.take_action(Raise(60)).is_err());
+    assert!(game.take_action(AllIn).is_err());
+    game.take_action(Call).unwrap();
+    assert_eq!(game.turn.0, Round::Flop);
+}
+
+#[test]
+fn raise_2() {
+    // A full all-in raise does reopen it
+    use crate::betting::Betting;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::NoLimit);
+    game.players[1].balance = 45;
+    game.take_action(Raise(30)).unwrap();
+    game.take_action(AllIn).unwrap();
+    game.take_action(Call).unwrap();
+    assert_eq!(game.raise_range(0), Some((70, 500)));
+}
+
+#[test]
+fn raise_3() {
+    // Bets open a street, raises answer them
+    use crate::betting::Betting;
+    use crate::playerinput::Action::*;
+    let mut game = betting_game(Betting::NoLimit);
+    assert!(game.take_action(Bet(20)).is_err());
+    for action in [Call, Call, Check] {
+        game.take_action(action).unwrap();
+    }
+    assert!(game.take_action(Bet(5)).is_err());
+    game.take_action(Bet(10)).unwrap();
+    assert!(game.take_action(Bet(30)).is_err());
+    game.take_action(Raise(30)).unwrap();
+    assert_eq!(game.bet, 30);
+    assert_eq!(game.last_raise, 20);
+}
+
+#[test]
+fn raise_4() {
+    // Typed actions, facing a bet of 30$
+    use crate::playerinput::{parse_action, Action::*};
+    assert_eq!(parse_action("raise 60", 30), Ok(Raise(60)));
+    assert_eq!(parse_action("raise to 60", 30), Ok(Raise(60)));
+    assert_eq!(parse_action("raise by 60", 30), Ok(Raise(90)));
+    assert_eq!(parse_action("bet 25$", 0), Ok(Bet(25)));
+    assert_eq!(parse_action("all in", 30), Ok(AllIn));
+    assert!(parse_action("raise by", 30).is_err());
+    assert!(parse_action("raise to -5", 30).is_err());
+    assert!(parse_action("raise by 4294967295", 30).is_err());
+}
+
+//
+// FORCED BET TESTS:
+//
+fn forced_bets_game(forced_bets: crate::betting::ForcedBets) -> crate::gameset::Game {
+    use crate::gameset::*;
+    let players = ["A", "B", "C", "D"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        forced_bets,
+        ..GameConfig::new(5)
+    };
+    Game::with_cards(players, config, 0, 1, DealtCards::default())
+}
+
+#[test]
+fn forced_bets_1() {
+    // Under the gun straddle: action starts left of it and the straddler gets the option
+    use crate::betting::*;
+    use crate::playerinput::Action::*;
+    let mut game = forced_bets_game(ForcedBets { straddle: Some(Straddle::UnderTheGun), ..Default::default() });
+    assert_eq!(game.straddler(), Some(3));
+    assert_eq!(game.pot, 35);
+    assert_eq!(game.bet, 20);
+    assert_eq!(game.turn.1, 0);
+    assert_eq!(game.raise_range(0), Some((40, 500)));
+    for action in [Call, Call, Call] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.turn.1, 3);
+    assert_eq!(game.raise_range(3), Some((40, 500)));
+}
+
+#[test]
+fn forced_bets_2() {
+    // Button straddle: the small blind acts first and the button last
+    use crate::betting::*;
+    use crate::playerinput::Action::*;
+    let mut game = forced_bets_game(ForcedBets { straddle: Some(Straddle::Button), ..Default::default() });
+    assert_eq!(game.straddler(), Some(0));
+    assert_eq!(game.turn.1, 1);
+    for action in [Call, Call, Call] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.turn.1, 0);
+    game.take_action(Check).unwrap();
+    assert_eq!(game.turn.0, crate::poker::Round::Flop);
+}
+
+#[test]
+fn forced_bets_3() {
+    // The big blind pays the ante for everyone, which doesn't count towards calling
+    use crate::betting::*;
+    use crate::events::GameEvent;
+    let game = forced_bets_game(ForcedBets { ante: 1, big_blind_ante: 10, straddle: None });
+    assert_eq!(game.pot, 4 + 15 + 10);
+    assert_eq!(game.players[2].balance, 500 - 1 - 10 - 10);
+    assert_eq!(game.players[2].bet, 10);
+    assert_eq!(game.bet, 10);
+    assert!(game.events.contains(&GameEvent::AntePosted { seat: 2, amount: 10 }));
+    // Forced bets go in before the cards are dealt
+    let dealt = game.events.iter().position(|e| matches!(e, GameEvent::HoleCardsDealt { .. }));
+    let posted = game.events.iter().rposition(|e| matches!(e, GameEvent::AntePosted { .. }));
+    assert!(posted < dealt);
+}
+
+#[test]
+fn forced_bets_4() {
+    // Straddles are saved in hand histories
+    use crate::betting::*;
+    use crate::events::GameEvent;
+    use crate::history::HandHistory;
+    let forced_bets = ForcedBets { straddle: Some(Straddle::UnderTheGun), ..Default::default() };
+    let game = forced_bets_game(forced_bets);
+    assert!(game.events.contains(&GameEvent::StraddlePosted { seat: 3, amount: 20 }));
+    let history = HandHistory::from_game(&game);
+    assert_eq!(history.straddle, Some((3, 20)));
+    let text = history.to_text();
+    assert!(text.contains("straddle_rule utg"));
+    assert_eq!(HandHistory::parse(&text), Ok(history));
+}
+
+//
+// RAKE TESTS:
+//
+fn rake_game(rake: crate::rake::Rake, balances: &[u32], holes: Vec<Vec<Card>>) -> crate::gameset::Game {
+    use crate::gameset::*;
+    let players = balances
+        .iter()
+        .zip(["A", "B", "C"])
+        .map(|(balance, name)| {
+            let mut player = Player::new(name.to_string());
+            player.balance = *balance;
+            player
+        })
+        .collect();
+    let config = GameConfig {
+        rake: Some(rake),
+        ..GameConfig::new(5)
+    };
+    let dealt = DealtCards {
+        holes,
+        upcards: Vec::new(),
+        board: cards("2c 7d 9h Js 3s"),
+    };
+    Game::with_cards(players, config, 0, 1, dealt)
+}
+
+#[test]
+fn rake_1() {
+    // Every pot is raked before it's awarded, side pots too
+    use crate::playerinput::Action::*;
+    use crate::rake::Rake;
+    let holes = vec![cards("Ah Ad"), cards("Kh Kd"), cards("Qh Qd")];
+    let mut game = rake_game(Rake::new(10.0), &[100, 300, 300], holes);
+    for action in [AllIn, AllIn, Call] {
+        game.take_action(action).unwrap();
+    }
+    assert!(game.ended);
+    assert_eq!(game.rake, 70);
+    assert_eq!(game.players[0].balance, 270);
+    assert_eq!(game.players[1].balance, 360);
+    assert_eq!(game.players[2].balance, 0);
+}
+
+#[test]
+fn rake_2() {
+    // The cap is for the whole hand, and no flop means no drop
+    use crate::playerinput::Action::*;
+    use crate::rake::Rake;
+    let holes = vec![cards("Ah Ad"), cards("Kh Kd"), cards("Qh Qd")];
+    let rake = Rake { percent: 10.0, cap: Some(20), no_flop_no_drop: true };
+    let mut game = rake_game(rake, &[100, 300, 300], holes.clone());
+    for action in [AllIn, AllIn, Call] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.rake, 20);
+    assert_eq!(game.players[0].balance + game.players[1].balance, 680);
+    let mut game = rake_game(rake, &[500, 500, 500], holes);
+    for action in [Fold, Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.rake, 0);
+    assert_eq!(game.players[2].balance, 505);
+}
+
+#[test]
+fn rake_3() {
+    // The rake rule and what was taken are saved in hand histories
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    use crate::rake::Rake;
+    let holes = vec![cards("Ah Ad"), cards("Kh Kd"), cards("Qh Qd")];
+    let rake = Rake { percent: 2.5, cap: Some(30), no_flop_no_drop: true };
+    let mut game = rake_game(rake, &[500, 500, 500], holes);
+    for action in [AllIn, Call, Fold] {
+        game.take_action(action).unwrap();
+    }
+    let history = game.history();
+    assert_eq!(history.rake, vec![(0, 25)]);
+    let text = history.to_text();
+    assert!(text.contains("rake_rule 2.5 cap 30 noflopnodrop"));
+    assert!(text.contains("rake 0 25"));
+    assert_eq!(HandHistory::parse(&text), Ok(history.clone()));
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+}
+
+#[test]
+fn rake_4() {
+    // The session keeps the house's total
+    use crate::gameset::*;
+    use crate::playerinput::{Action::*, ScriptedController};
+    use crate::rake::Rake;
+    use crate::session::Session;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::with_controller(name.to_string(), Box::new(ScriptedController::new(vec![AllIn]))))
+        .collect();
+    let config = GameConfig {
+        rake: Some(Rake { percent: 5.0, cap: Some(20), no_flop_no_drop: false }),
+        ..GameConfig::new(5)
+    };
+    let mut session = Session::with_config(players, config, Some(1));
+    session.play_hand();
+    assert_eq!(session.game.rake, 20);
+    assert_eq!(session.house_total, 20);
+    assert_eq!(Rake::new(5.0).take(19, 0), 0);
+}
+
+#[test]
+fn rake_5() {
+    // Uncalled chips come back without being raked
+    use crate::playerinput::Action::*;
+    use crate::rake::Rake;
+    let holes = vec![cards("Ah Ad"), cards("Kh Kd"), cards("Qh Qd")];
+    let mut game = rake_game(Rake::new(10.0), &[100, 300, 300], holes);
+    for action in [AllIn, AllIn, Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.pots().len(), 1);
+    assert_eq!(game.history().rake, vec![(0, 21)]);
+    assert_eq!(game.players[1].balance, 200);
+}
+
+#[test]
+fn rake_6() {
+    // A bet everyone folds to comes back before the rake, whatever the street
+    use crate::events::GameEvent;
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    use crate::rake::Rake;
+    let holes = vec![cards("Ah Ad"), cards("Kh Kd"), cards("Qh Qd")];
+    let mut game = rake_game(Rake::new(10.0), &[500, 500, 500], holes);
+    for action in [Call, Call, Check, Bet(200), Fold, Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert!(game.ended);
+    assert!(game.events.contains(&GameEvent::UncalledReturned { seat: 1, amount: 200 }));
+    assert_eq!(game.rake, 3);
+    assert_eq!(game.players[1].balance, 517);
+    let history = game.history();
+    assert_eq!(history.uncalled, Some((1, 200)));
+    assert!(history.to_text().contains("uncalled 1 200"));
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+}
+
+#[test]
+fn rake_7() {
+    // A side pot only one seat can win is still raked for the folded chips in it
+    use crate::playerinput::Action::*;
+    use crate::rake::Rake;
+    let holes = vec![cards("Ah Ad"), cards("Kh Kd"), cards("Qh Qd")];
+    let mut game = rake_game(Rake::new(10.0), &[50, 500, 500], holes);
+    for action in [AllIn, Raise(200), Call, Bet(100), Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert!(game.ended);
+    assert_eq!(game.history().rake, vec![(0, 15), (1, 30)]);
+    assert_eq!(game.players[0].balance, 135);
+    assert_eq!(game.players[1].balance, 570);
+    assert_eq!(game.players[2].balance, 300);
+}
+
+//
+// ODD CHIP TESTS:
+//
+fn odd_chip_game(variant: crate::variant::Variant, dealt: crate::gameset::DealtCards) -> crate::gameset::Game {
+    use crate::gameset::*;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        variant,
+        forced_bets: crate::betting::ForcedBets { ante: 1, ..Default::default() },
+        ..GameConfig::new(5)
+    };
+    Game::with_cards(players, config, 0, 1, dealt)
+}
+
+fn royal_board() -> crate::gameset::DealtCards {
+    crate::gameset::DealtCards {
+        holes: vec![cards("2c 3c"), cards("2d 3d"), cards("2s 3s")],
+        upcards: Vec::new(),
+        board: cards("Ah Kh Qh Jh Th"),
+    }
+}
+
+#[test]
+fn odd_chip_1() {
+    // Everyone plays the board, the first winner left of the button gets the odd chip
+    use crate::playerinput::Action::*;
+    use crate::variant::Variant;
+    let mut game = odd_chip_game(Variant::Holdem, royal_board());
+    game.take_action(Fold).unwrap();
+    while !game.ended {
+        game.take_action(if game.bet > game.players[game.turn.1].bet { Call } else { Check }).unwrap();
+    }
+    // 3$ of antes and two big blinds
+    assert_eq!(game.history().awards, vec![(0, 1, 12), (0, 2, 11)]);
+    assert_eq!(game.players[1].balance, 501);
+    assert_eq!(game.players[2].balance, 500);
+}
+
+#[test]
+fn odd_chip_2() {
+    // Left of the button comes before the button itself
+    use crate::playerinput::Action::*;
+    use crate::variant::Variant;
+    let mut game = odd_chip_game(Variant::Holdem, royal_board());
+    game.take_action(Call).unwrap();
+    game.take_action(Call).unwrap();
+    game.take_action(Fold).unwrap();
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    // 3$ of antes, two calls and the folded big blind
+    assert_eq!(game.history().awards, vec![(0, 0, 16), (0, 1, 17)]);
+}
+
+#[test]
+fn odd_chip_3() {
+    // In stud the odd chip goes to the highest card, the Ace of Hearts beats the Ace of Diamonds
+    use crate::gameset::DealtCards;
+    use crate::playerinput::Action::*;
+    use crate::variant::Variant;
+    let dealt = DealtCards {
+        holes: vec![cards("Ah Kh 9s"), cards("Ad Kd 9h"), cards("5s 6s 7c")],
+        upcards: vec![cards("2c Qd Jc 3d"), cards("2d Qc Js 3c"), cards("Th 8d 7d 6c")],
+        board: Vec::new(),
+    };
+    let mut game = odd_chip_game(Variant::Stud, dealt);
+    assert_eq!(game.history().bring_in, Some((0, 5)));
+    game.take_action(Call).unwrap();
+    game.take_action(Fold).unwrap();
+    while !game.ended {
+        game.take_action(Check).unwrap();
+    }
+    assert_eq!(game.history().awards, vec![(0, 0, 7), (0, 1, 6)]);
+}
+
+#[test]
+fn odd_chip_4() {
+    // The ledger adds up after every hand of a session
+    use crate::gameset::*;
+    use crate::playerinput::PassiveBot;
+    use crate::session::Session;
+    let players = ["A", "B", "C", "D"]
+        .iter()
+        .map(|name| Player::with_controller(name.to_string(), Box::new(PassiveBot)))
+        .collect();
+    let config = GameConfig {
+        rake: Some(crate::rake::Rake::new(3.0)),
+        ..GameConfig::new(5)
+    };
+    let mut session = Session::with_seed(players, config, Some(20), 11);
+    for _ in 0..20 {
+        session.play_hand();
+        assert_eq!(session.game.chip_total(), session.game.chips);
+        let stacks: u32 = session.players().iter().map(|p| p.balance).sum();
+        assert_eq!(stacks + session.house_total, 2000);
+        if session.finished() {
+            break;
+        }
+        session.next_hand();
+    }
+}
+
+//
+// RUN IT TWICE TESTS:
+//
+fn run_game(runs: usize, board: Vec<Card>) -> crate::gameset::Game {
+    use crate::gameset::*;
+    let players = ["A", "B", "C"]
+        .iter()
+        .map(|name| Player::new(name.to_string()))
+        .collect();
+    let config = GameConfig {
+        runs,
+        ..GameConfig::new(5)
+    };
+    let dealt = DealtCards {
+        holes: vec![cards("Ah Ad"), cards("Kh Kd"), cards("7c 2d")],
+        upcards: Vec::new(),
+        board,
+    };
+    Game::with_cards(players, config, 0, 1, dealt)
+}
+
+#[test]
+fn run_1() {
+    // All-in preflop and run twice: each run wins half the pot
+    use crate::events::GameEvent;
+    use crate::playerinput::Action::*;
+    let mut game = run_game(2, cards("2c 7s 9h Js 3s Kc 8d 9c Qc 4d"));
+    for action in [AllIn, Call, Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert!(game.ended);
+    assert_eq!(game.table, cards("2c 7s 9h Js 3s"));
+    assert_eq!(game.boards()[1], cards("Kc 8d 9c Qc 4d"));
+    assert!(game.events.contains(&GameEvent::BoardRun { run: 2, cards: cards("Kc 8d 9c Qc 4d") }));
+    assert_eq!(game.history().awards, vec![(0, 0, 505), (0, 1, 505)]);
+}
+
+#[test]
+fn run_2() {
+    // The board is dealt street by street, and only the rest of it is run again
+    use crate::playerinput::Action::*;
+    let mut game = run_game(3, cards("2c 7s 9h Js 3s Kc 4d 5c Qc"));
+    assert!(game.table.is_empty());
+    for action in [Call, Call, Check] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.table, cards("2c 7s 9h"));
+    assert_eq!(game.view(1).table, cards("2c 7s 9h"));
+    for action in [AllIn, AllIn, Fold] {
+        game.take_action(action).unwrap();
+    }
+    let boards = game.boards();
+    assert_eq!(boards.len(), 3);
+    assert_eq!(boards[1], cards("2c 7s 9h Kc 4d"));
+    assert_eq!(boards[2], cards("2c 7s 9h 5c Qc"));
+    // Two pair beats the kings on the first and last runs, the odd chips go to the first runs
+    assert_eq!(game.history().awards, vec![(0, 2, 337), (0, 1, 337), (0, 2, 336)]);
+}
+
+#[test]
+fn run_3() {
+    // Runs are only dealt while cards are still to come, and are saved in hand histories
+    use crate::history::*;
+    use crate::playerinput::Action::*;
+    let mut game = run_game(2, cards("2c 7s 9h Js 3s Kc 4d"));
+    for action in [Call, Call, Check, Check, Check, AllIn, Call, Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert_eq!(game.boards().len(), 2);
+    let history = game.history();
+    assert_eq!(history.extra_runs, vec![cards("Kc 4d")]);
+    let text = history.to_text();
+    assert!(text.contains("runs 2"));
+    assert!(text.contains("run Kc 4d"));
+    assert_eq!(HandHistory::parse(&text), Ok(history.clone()));
+    assert_eq!(replay(&history, Vec::new()), Ok(()));
+
+    let mut game = run_game(2, cards("2c 7s 9h Js 3s"));
+    for action in [Call, Call, Check] {
+        game.take_action(action).unwrap();
+    }
+    while game.turn.0 != Round::River {
+        game.take_action(Check).unwrap();
+    }
+    for action in [AllIn, Call, Fold] {
+        game.take_action(action).unwrap();
+    }
+    assert!(game.ended);
+    assert_eq!(game.boards().len(), 1);
+}
@@ -0,0 +1,184 @@
+// variant.rs
+use crate::hands::*;
+use crate::poker::*;
+use std::cmp::Ordering;
+
+// The poker game being played.
+// Omaha(n): n hole cards (4, 5 or 6), the hand must use exactly two of them.
+// ShortDeck: Hold'em without Two to Five, a flush beats a full house.
+// Stud: Seven-card stud, no community cards, each player gets 3 face-down and 4 face-up cards.
+// OmahaHiLo and StudHiLo split each pot between the best high and the best 8-or-better low.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Variant {
+    #[default]
+    Holdem,
+    Omaha(usize),
+    OmahaHiLo(usize),
+    ShortDeck,
+    Stud,
+    StudHiLo,
+}
+
+// Highest card a hi-lo low can have
+pub const LOW_QUALIFIER: Rank = Rank::Eight;
+
+impl Variant {
+    // Cards dealt to each player before the first betting round.
+    // Stud players get 2 of them face down, plus one face up.
+    pub fn hole_size(&self) -> usize {
+        match self {
+            Variant::Holdem | Variant::ShortDeck | Variant::Stud | Variant::StudHiLo => HOLE_SIZE,
+            Variant::Omaha(size) | Variant::OmahaHiLo(size) => *size,
+        }
+    }
+
+    // Cards each player gets over the whole hand
+    pub fn cards_per_player(&self) -> usize {
+        match self {
+            Variant::Stud | Variant::StudHiLo => 7,
+            _ => self.hole_size(),
+        }
+    }
+
+    pub fn table_size(&self) -> usize {
+        match self {
+            Variant::Stud | Variant::StudHiLo => 0,
+            _ => TABLE_SIZE,
+        }
+    }
+
+    pub fn first_round(&self) -> Round {
+        match self {
+            Variant::Stud | Variant::StudHiLo => Round::ThirdStreet,
+            _ => Round::PreFlop,
+        }
+    }
+
+    // Ranks the deck is built from
+    pub fn ranks(&self) -> &'static [Rank] {
+        match self {
+            Variant::ShortDeck => &RANKS[4..],
+            _ => &RANKS,
+        }
+    }
+
+    pub fn deck_size(&self) -> usize {
+        self.ranks().len() * SUITS.len()
+    }
+
+    // Most players that can be dealt in without running out of cards
+    pub fn max_players(&self) -> usize {
+        (self.deck_size() - self.table_size()) / self.cards_per_player()
+    }
+
+    // Best hand a player can make with their hole cards and the board
+    pub fn best_hand(&self, hole: &[Card], board: &[Card]) -> BestHand {
+        match self {
+            Variant::Holdem | Variant::Stud | Variant::StudHiLo => {
+                let cards: Vec<Card> = hole.iter().chain(board.iter()).copied().collect();
+                calculate_best_hand(&cards)
+            }
+            Variant::Omaha(_) | Variant::OmahaHiLo(_) => calculate_omaha_best_hand(hole, board),
+            Variant::ShortDeck => {
+                let cards: Vec<Card> = hole.iter().chain(board.iter()).copied().collect();
+                calculate_short_deck_best_hand(&cards)
+            }
+        }
+    }
+
+    // Same as best_hand without picking the cards, for simulations
+    pub fn hand(&self, hole: &[Card], board: &[Card]) -> Hand {
+        match self {
+            Variant::Omaha(_) | Variant::OmahaHiLo(_) => calculate_omaha_hand(hole, board),
+            _ => {
+                let cards: Vec<Card> = hole.iter().chain(board.iter()).copied().collect();
+                match self {
+                    Variant::ShortDeck => calculate_short_deck_hand(&cards),
+                    _ => calculate_hand(&cards),
+                }
+            }
+        }
+    }
+
+    // Same as best_hand when jokers, and cards of the wild ranks, stand for any card
+    pub fn wild_best_hand(&self, hole: &[Card], board: &[Card], wild: &[Rank]) -> BestHand {
+        let wheel = match self {
+            Variant::ShortDeck => &SHORT_DECK_WHEEL,
+            _ => &WHEEL,
+        };
+        five_card_hands(hole, board, self.is_omaha())
+            .into_iter()
+            .map(|cards| BestHand {
+                hand: wild_hands(&cards, wild, wheel)
+                    .into_iter()
+                    .max_by(|a, b| self.compare_hands(a, b))
+                    .unwrap(),
+                cards,
+            })
+            .max_by(|a, b| self.compare_hands(&a.hand, &b.hand))
+            .expect("Not enough cards for a hand")
+    }
+
+    // Best qualifying low, only in hi-lo games
+    pub fn low_hand(&self, hole: &[Card], board: &[Card]) -> Option<LowHand> {
+        match self {
+            Variant::OmahaHiLo(_) => calculate_omaha_low(hole, board, Some(LOW_QUALIFIER)),
+            Variant::StudHiLo => {
+                let cards: Vec<Card> = hole.iter().chain(board.iter()).copied().collect();
+                calculate_ace_to_five_low(&cards, Some(LOW_QUALIFIER))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_hi_lo(&self) -> bool {
+        matches!(self, Variant::OmahaHiLo(_) | Variant::StudHiLo)
+    }
+
+    pub fn is_stud(&self) -> bool {
+        matches!(self, Variant::Stud | Variant::StudHiLo)
+    }
+
+    // Hands must use exactly two hole cards
+    pub fn is_omaha(&self) -> bool {
+        matches!(self, Variant::Omaha(_) | Variant::OmahaHiLo(_))
+    }
+
+    pub fn compare_hands(&self, hand: &Hand, other: &Hand) -> Ordering {
+        match self {
+            Variant::ShortDeck => match (hand, other) {
+                (Hand::Flush(..), Hand::FullHouse(..)) => Ordering::Greater,
+                (Hand::FullHouse(..), Hand::Flush(..)) => Ordering::Less,
+                _ => hand.compare(other),
+            },
+            _ => hand.compare(other),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Variant::Holdem => "holdem".to_string(),
+            Variant::Omaha(size) => format!("omaha{}", size),
+            Variant::OmahaHiLo(size) => format!("omaha{}hilo", size),
+            Variant::ShortDeck => "shortdeck".to_string(),
+            Variant::Stud => "stud".to_string(),
+            Variant::StudHiLo => "studhilo".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name.to_lowercase().as_str() {
+            "holdem" => Some(Variant::Holdem),
+            "omaha" | "omaha4" | "plo" | "plo4" => Some(Variant::Omaha(4)),
+            "omaha5" | "plo5" => Some(Variant::Omaha(5)),
+            "omaha6" | "plo6" => Some(Variant::Omaha(6)),
+            "omahahilo" | "omaha4hilo" | "omaha8" | "plo8" => Some(Variant::OmahaHiLo(4)),
+            "omaha5hilo" => Some(Variant::OmahaHiLo(5)),
+            "omaha6hilo" => Some(Variant::OmahaHiLo(6)),
+            "shortdeck" | "short" | "6+" => Some(Variant::ShortDeck),
+            "stud" | "7stud" => Some(Variant::Stud),
+            "studhilo" | "stud8" => Some(Variant::StudHiLo),
+            _ => None,
+        }
+    }
+}